@@ -1,5 +1,7 @@
 use clap::{value_parser, Arg, ArgAction, Command};
 
+use crate::tree::{Depth, Order};
+
 pub use super::ls_handler::handle_ls;
 
 pub fn ls_subcommand() -> Command {
@@ -13,8 +15,36 @@ pub fn ls_subcommand() -> Command {
         )
         .arg(Arg::new("query").long("query").short('q').help(
             "Search or filter objects based on a pattern. E.g. 'foo', \
-             'foo.*', '.*bar'",
+             'handle_*', '.*bar'. Prefix with 're:' or 'glob:' to force the \
+             match mode, e.g. 'glob:handle_*' or 're:Writer+'",
         ))
+        .arg(
+            Arg::new("match")
+                .long("match")
+                .short('m')
+                .value_parser(["exact", "regex", "glob"])
+                .help(
+                    "How to match --query against item paths. Defaults to \
+                     glob if the only wildcards are '*' and '?', regex if \
+                     the query contains other regex characters, else exact.",
+                ),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .short('f')
+                .value_parser(["text", "json", "markdown", "md", "xml"])
+                .help(
+                    "Output format. 'json' lists items, dependencies and \
+                     dependents with their kind, path, file, span and \
+                     source, plus the edges between them. 'markdown' puts \
+                     each item in a fenced code block under a heading per \
+                     file. 'xml' wraps each item in an <item> element \
+                     with its path, kind, file and lines, grouped into \
+                     <query>, <dependencies> and <dependents>. Defaults to \
+                     text.",
+                ),
+        )
         .arg(
             Arg::new("silent")
                 .long("silent")
@@ -39,6 +69,144 @@ pub fn ls_subcommand() -> Command {
                 .help("Include dependents in the output.")
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("owners")
+                .long("owners")
+                .help(
+                    "With --used-by, print the definition (without methods) \
+                     of the type each dependent method belongs to.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("depth")
+                .long("depth")
+                .value_name("N|all")
+                .value_parser(|s: &str| s.parse::<Depth>())
+                .help(
+                    "Follow dependencies of dependencies (--depends-on), or \
+                     dependents of dependents (--used-by), up to N levels, \
+                     or all of them. Defaults to 1 (direct ones only).",
+                ),
+        )
+        .arg(
+            Arg::new("order")
+                .long("order")
+                .value_name("path|position|topo")
+                .value_parser(|s: &str| s.parse::<Order>())
+                .help(
+                    "Order of the items within a file, the dependencies and \
+                     the dependents: by item path (default), by source \
+                     position, or topologically, each dependency before the \
+                     items requiring it.",
+                ),
+        )
+        .arg(
+            Arg::new("ids")
+                .long("ids")
+                .help(
+                    "Print the id of each item, e.g. \
+                     'fn:codeclippy::map::list_map'. An id can be passed to \
+                     --query to select exactly that item.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("line-numbers")
+                .long("line-numbers")
+                .short('n')
+                .help(
+                    "Prefix each line of code with its line number in the \
+                     source file.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("verbatim")
+                .long("verbatim")
+                .help(
+                    "Copy items from their source files as they are, \
+                     including attributes, doc comments and formatting, \
+                     instead of reconstructing them.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("skeleton")
+                .long("skeleton")
+                .help(
+                    "Print the signatures of functions and methods without \
+                     their bodies, followed by ';'. Structs keep their \
+                     fields, enums their variants and traits the \
+                     signatures of their methods. Useful to fit the shape \
+                     of a whole crate in a small context.",
+                )
+                .conflicts_with("verbatim")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("budget")
+                .long("budget")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help(
+                    "Keep the output within N tokens. Dependents, then \
+                     transitive dependencies are reduced to their skeleton \
+                     (see --skeleton), or left out if that is not enough.",
+                ),
+        )
+        .arg(
+            Arg::new("tokenizer")
+                .long("tokenizer")
+                .value_name("path")
+                .help(
+                    "Count tokens with the BPE merges in a tokenizer.json \
+                     or merges.txt file, instead of an approximation.",
+                ),
+        )
+        .arg(
+            Arg::new("count-tokens")
+                .long("count-tokens")
+                .help(
+                    "Report the number of tokens of each item, and the \
+                     total, on stderr.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("docs")
+                .long("docs")
+                .help("Print the doc comment of each item.")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("attrs")
+                .long("attrs")
+                .help(
+                    "Print the attributes of each item, e.g. \
+                     '#[derive(Debug)]'.",
+                )
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("with-attr")
+                .long("with-attr")
+                .value_name("regex")
+                .help(
+                    "Only list items with an attribute matching the regex, \
+                     e.g. 'derive\\(.*Serialize' or 'deprecated'. Without \
+                     --query, all items are searched.",
+                ),
+        )
+        .arg(
+            Arg::new("with-doc")
+                .long("with-doc")
+                .value_name("regex")
+                .help(
+                    "Only list items with a doc comment matching the regex. \
+                     Without --query, all items are searched.",
+                ),
+        )
         .arg(
             Arg::new("clip")
                 .long("clip")
@@ -47,13 +215,67 @@ pub fn ls_subcommand() -> Command {
                 .default_missing_value("clipboard://")
                 .help(
                     "Copy the output to the clipboard, or the specified URI \
-                     if provided.",
+                     if provided: 'clipboard://', 'file://path' (replaces \
+                     the file), 'file://path?append', 'stdout://' or '-', \
+                     and 'stderr://'.",
                 ),
         )
+        .arg(
+            Arg::new("split-by")
+                .long("split-by")
+                .value_name("unit")
+                .value_parser(["tokens", "bytes", "items"])
+                .requires("chunk-size")
+                .requires("out-dir")
+                .conflicts_with("clip")
+                .help(
+                    "Split the output into numbered chunk files of at most \
+                     --chunk-size tokens, bytes or items, written to \
+                     --out-dir with an index.json listing the items in \
+                     each. Items are never split. Only for --format text.",
+                ),
+        )
+        .arg(
+            Arg::new("chunk-size")
+                .long("chunk-size")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .requires("split-by")
+                .help("Maximum size of a chunk, see --split-by."),
+        )
+        .arg(
+            Arg::new("out-dir")
+                .long("out-dir")
+                .value_name("path")
+                .requires("split-by")
+                .help("Directory to write chunks to, see --split-by."),
+        )
         .arg(
             Arg::new("maxdepth")
                 .value_parser(value_parser!(usize))
                 .long("maxdepth")
                 .help("Set max depth of subdirectories to traverse"),
         )
+        .arg(
+            Arg::new("include")
+                .long("include")
+                .value_name("glob")
+                .action(ArgAction::Append)
+                .help(
+                    "Only list files matching the glob, relative to the \
+                     path, e.g. 'tree/**'. Can be repeated.",
+                ),
+        )
+        .arg(
+            Arg::new("exclude")
+                .long("exclude")
+                .value_name("glob")
+                .action(ArgAction::Append)
+                .help(
+                    "Skip files and directories matching the glob, e.g. \
+                     '**/tests'. Can be repeated. Hidden files, build \
+                     directories and files ignored by .gitignore or \
+                     .ignore are always skipped.",
+                ),
+        )
 }
@@ -1,11 +1,52 @@
-use crate::map::list_map;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::localfs::FileFilter;
+use crate::map::{list_map, ListOptions};
+use crate::output::OutputFormat;
+use crate::query::{MatchMode, Query};
+use crate::tokenizer::{ApproximateTokenizer, BpeTokenizer, Tokenizer};
+use crate::tree::{Depth, Order};
+use crate::types::CodeStyle;
 use crate::writers::*;
 
 pub fn handle_ls(ls_matches: &clap::ArgMatches) {
     let uri = ls_matches.get_one::<String>("uri").unwrap().to_string();
-    let filter_name = ls_matches
-        .get_one::<String>("query")
-        .map(ToString::to_string);
+    let match_mode = ls_matches
+        .get_one::<String>("match")
+        .and_then(|mode| MatchMode::from_name(mode));
+    let with_attr = ls_matches.get_one::<String>("with-attr");
+    let with_doc = ls_matches.get_one::<String>("with-doc");
+    // without a query, the attribute filters select from all items
+    let query = match ls_matches.get_one::<String>("query") {
+        Some(query) => Some(query.as_str()),
+        None if with_attr.is_some() || with_doc.is_some() => Some("re:.*"),
+        None => None,
+    };
+    let query = query.map(|query| {
+        let mut query = Query::new(query, match_mode).unwrap_or_else(|e| {
+            eprintln!("Invalid query '{}': {}", query, e);
+            std::process::exit(1);
+        });
+        if let Some(pattern) = with_attr {
+            query = query.with_attr(pattern).unwrap_or_else(|e| {
+                eprintln!("Invalid --with-attr '{}': {}", pattern, e);
+                std::process::exit(1);
+            });
+        }
+        if let Some(pattern) = with_doc {
+            query = query.with_doc(pattern).unwrap_or_else(|e| {
+                eprintln!("Invalid --with-doc '{}': {}", pattern, e);
+                std::process::exit(1);
+            });
+        }
+        query
+    });
+
+    let format = ls_matches
+        .get_one::<String>("format")
+        .and_then(|format| OutputFormat::from_name(format))
+        .unwrap_or_default();
 
     let show_dependencies =
         *ls_matches.get_one::<bool>("depends-on").unwrap_or(&false);
@@ -15,24 +56,107 @@ pub fn handle_ls(ls_matches: &clap::ArgMatches) {
 
     let silence_query = *ls_matches.get_one::<bool>("silent").unwrap_or(&false);
 
+    let show_ids = *ls_matches.get_one::<bool>("ids").unwrap_or(&false);
+
+    let show_owners = *ls_matches.get_one::<bool>("owners").unwrap_or(&false);
+
+    let code_style = CodeStyle {
+        line_numbers: *ls_matches
+            .get_one::<bool>("line-numbers")
+            .unwrap_or(&false),
+        verbatim: *ls_matches.get_one::<bool>("verbatim").unwrap_or(&false),
+        docs: *ls_matches.get_one::<bool>("docs").unwrap_or(&false),
+        attrs: *ls_matches.get_one::<bool>("attrs").unwrap_or(&false),
+        skeleton: *ls_matches.get_one::<bool>("skeleton").unwrap_or(&false),
+    };
+
+    let tokenizer = ls_matches.get_one::<String>("tokenizer").map(|path| {
+        let tokenizer = BpeTokenizer::from_file(Path::new(path))
+            .unwrap_or_else(|e| {
+                eprintln!("Failed to load tokenizer '{}': {}", path, e);
+                std::process::exit(1);
+            });
+        Rc::new(tokenizer) as Rc<dyn Tokenizer>
+    });
+
     let target_uri = ls_matches
         .get_one::<String>("clip")
         .map(ToString::to_string);
 
-    let mut writer: Box<dyn ClippyWriter> = match target_uri.as_deref() {
-        Some("clipboard://") => Box::new(ClipboardWriter::new()),
-        None => Box::new(StdoutWriter::new()),
-        _ => panic!("Target URI for --clip not supported"),
+    let split_by = ls_matches
+        .get_one::<String>("split-by")
+        .and_then(|unit| SplitBy::from_name(unit));
+    if split_by.is_some() && format != OutputFormat::Text {
+        eprintln!("--split-by only supports --format text");
+        std::process::exit(1);
+    }
+
+    let globs = |name: &str| -> Vec<String> {
+        ls_matches
+            .get_many::<String>(name)
+            .map(|globs| globs.cloned().collect())
+            .unwrap_or_default()
     };
+    let files = FileFilter::new(
+        ls_matches.get_one::<usize>("maxdepth").copied(),
+        globs("include"),
+        globs("exclude"),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Invalid --include or --exclude glob: {}", e);
+        std::process::exit(1);
+    });
 
-    let maxdepth = ls_matches.get_one::<usize>("maxdepth");
-    list_map(
-        &uri,
-        filter_name.as_deref(),
-        &mut writer,
+    let mut writer: Box<dyn ClippyWriter> = match (split_by, target_uri) {
+        (Some(split_by), _) => {
+            let out_dir = ls_matches.get_one::<String>("out-dir").unwrap();
+            let chunk_writer = ChunkWriter::new(
+                Path::new(out_dir),
+                split_by,
+                *ls_matches.get_one::<usize>("chunk-size").unwrap(),
+                tokenizer
+                    .clone()
+                    .unwrap_or_else(|| Rc::new(ApproximateTokenizer::new())),
+            )
+            .unwrap_or_else(|e| {
+                eprintln!("Invalid --out-dir '{}': {}", out_dir, e);
+                std::process::exit(1);
+            });
+            Box::new(chunk_writer)
+        }
+        (None, Some(uri)) => writer_for_uri(&uri).unwrap_or_else(|e| {
+            eprintln!("Invalid --clip target '{}': {}", uri, e);
+            std::process::exit(1);
+        }),
+        (None, None) => Box::new(StdoutWriter::new()),
+    };
+
+    let options = ListOptions {
         silence_query,
         show_dependencies,
         show_dependents,
-        maxdepth.copied(),
-    );
+        show_ids,
+        show_owners,
+        code_style,
+        files,
+        depth: ls_matches
+            .get_one::<Depth>("depth")
+            .copied()
+            .unwrap_or_default(),
+        order: ls_matches
+            .get_one::<Order>("order")
+            .copied()
+            .unwrap_or_default(),
+        format,
+        budget: ls_matches.get_one::<usize>("budget").copied(),
+        count_tokens: *ls_matches
+            .get_one::<bool>("count-tokens")
+            .unwrap_or(&false),
+        tokenizer,
+    };
+    list_map(&uri, query.as_ref(), &mut writer, &options);
+    if let Err(e) = writer.flush() {
+        eprintln!("Failed to write output: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -1,42 +1,107 @@
 use std::fs;
 use std::path::Path;
 
+use proc_macro2::{Delimiter, TokenTree};
 use syn::__private::ToTokens;
+use syn::spanned::Spanned;
 use syn::visit::Visit;
 use syn::{File, ImplItem, Item, TraitItem};
 
 use crate::helpers::generate_id;
 use crate::localfs::FilePath;
+use crate::modules::{resolve_module_paths, ModDeclaration, UseDeclaration};
 use crate::types::{
-    Identifiable, RustEnum, RustFunction, RustImpl, RustStruct, RustTrait,
-    Visibility,
+    Attributes, Field, Identifiable, RustConst, RustEnum, RustFunction,
+    RustImpl, RustMacro, RustStatic, RustStruct, RustTrait, RustTypeAlias,
+    Span, StructKind, Visibility,
 };
 
 #[derive(Debug, Clone)]
 pub struct RustFileVisitor {
     file_path: FilePath,
+    // full module path, e.g. ["codeclippy", "tree", "builder"]
+    module_path: Vec<String>,
+    // inline modules within the file, e.g. ["tests"] for `mod tests {}`
+    inline_path: Vec<String>,
+    inline_modules: Vec<RustFileVisitor>,
+    pub mod_declarations: Vec<ModDeclaration>,
+    pub use_declarations: Vec<UseDeclaration>,
     pub functions: Vec<RustFunction>,
     pub structs: Vec<RustStruct>,
     pub enums: Vec<RustEnum>,
     pub traits: Vec<RustTrait>,
     pub impls: Vec<RustImpl>,
+    pub consts: Vec<RustConst>,
+    pub statics: Vec<RustStatic>,
+    pub type_aliases: Vec<RustTypeAlias>,
+    pub macros: Vec<RustMacro>,
 }
 
 impl RustFileVisitor {
     pub fn new(file_path: FilePath) -> Self {
         RustFileVisitor {
             file_path,
+            module_path: Vec::new(),
+            inline_path: Vec::new(),
+            inline_modules: Vec::new(),
+            mod_declarations: Vec::new(),
+            use_declarations: Vec::new(),
             functions: Vec::new(),
             structs: Vec::new(),
             enums: Vec::new(),
             traits: Vec::new(),
             impls: Vec::new(),
+            consts: Vec::new(),
+            statics: Vec::new(),
+            type_aliases: Vec::new(),
+            macros: Vec::new(),
         }
     }
 
     pub fn file_path(&self) -> &FilePath {
         &self.file_path
     }
+
+    pub fn module_path(&self) -> &Vec<String> {
+        &self.module_path
+    }
+
+    pub fn set_module_path(&mut self, module_path: Vec<String>) {
+        self.module_path = module_path;
+    }
+
+    pub fn inline_path(&self) -> &Vec<String> {
+        &self.inline_path
+    }
+
+    /// Make the ids of all items unique across the crate, e.g.
+    /// `fn:codeclippy::tree::TreeBuilder::new`. Requires the module path
+    /// to be set.
+    pub fn qualify_ids(&mut self) {
+        let scope = self.module_path.join("::");
+
+        self.functions.iter_mut().for_each(|f| f.qualify_id(&scope));
+        self.structs.iter_mut().for_each(|s| s.qualify_id(&scope));
+        self.enums.iter_mut().for_each(|e| e.qualify_id(&scope));
+        self.traits.iter_mut().for_each(|t| t.qualify_id(&scope));
+        self.impls.iter_mut().for_each(|i| i.qualify_id(&scope));
+        self.consts.iter_mut().for_each(|c| c.qualify_id(&scope));
+        self.statics.iter_mut().for_each(|s| s.qualify_id(&scope));
+        self.type_aliases
+            .iter_mut()
+            .for_each(|t| t.qualify_id(&scope));
+        self.macros.iter_mut().for_each(|m| m.qualify_id(&scope));
+    }
+
+    // the visitor itself, followed by all its (nested) inline modules
+    fn into_flattened(mut self) -> Vec<RustFileVisitor> {
+        let inline_modules = std::mem::take(&mut self.inline_modules);
+        let mut visitors = vec![self];
+        for inline_module in inline_modules {
+            visitors.extend(inline_module.into_flattened());
+        }
+        visitors
+    }
 }
 
 impl RustFileVisitor {
@@ -52,11 +117,18 @@ impl RustFileVisitor {
             let mut visitor = RustFileVisitor::new(file_path);
             let syntax_tree: File = syn::parse_file(&content)?;
             visitor.visit_file(&syntax_tree);
-            // associate methods with their structs and enums
-            visitor.associate_methods();
-            visitors.push(visitor);
+            // inline modules are handled as separate visitors
+            for mut module_visitor in visitor.into_flattened() {
+                // associate methods with their structs and enums
+                module_visitor.associate_methods();
+                visitors.push(module_visitor);
+            }
         }
 
+        resolve_module_paths(base_directory, &mut visitors);
+        for visitor in &mut visitors {
+            visitor.qualify_ids();
+        }
         Ok(visitors)
     }
 
@@ -65,7 +137,7 @@ impl RustFileVisitor {
             if let Some(struct_to_update) = self
                 .structs
                 .iter_mut()
-                .find(|struct_item| struct_item.name() == rust_impl.for_type)
+                .find(|struct_item| struct_item.name() == rust_impl.type_path())
             {
                 struct_to_update.add_methods(
                     rust_impl.methods.clone().unwrap_or_else(Vec::new),
@@ -79,7 +151,7 @@ impl RustFileVisitor {
             if let Some(enum_to_update) = self
                 .enums
                 .iter_mut()
-                .find(|enum_item| enum_item.name() == rust_impl.for_type)
+                .find(|enum_item| enum_item.name() == rust_impl.type_path())
             {
                 enum_to_update.add_methods(
                     rust_impl.methods.clone().unwrap_or_else(Vec::new),
@@ -106,27 +178,42 @@ impl<'ast> Visit<'ast> for RustFileVisitor {
                     Some(&func.vis),
                     Some(self.file_path().clone()),
                     Some(func.block.clone()),
+                    item_span(func),
+                    &func.attrs,
                 );
                 self.functions.push(rust_function);
             }
             Item::Struct(struct_item) => {
-                let fields = struct_item
-                    .fields
-                    .iter()
-                    .map(|field| {
-                        (
-                            field.ident.as_ref().unwrap().to_string(),
-                            format!("{}", field.ty.to_token_stream()),
-                        )
-                    })
-                    .collect::<Vec<_>>();
-
-                let mut rust_struct = RustStruct::new(
-                    &generate_id(&struct_item.ident.to_string()),
-                    visibility_to_local_version(&struct_item.vis),
-                    &struct_item.ident.to_string(),
+                let kind = match &struct_item.fields {
+                    syn::Fields::Named(_) => StructKind::Named,
+                    syn::Fields::Unnamed(_) => StructKind::Tuple,
+                    syn::Fields::Unit => StructKind::Unit,
+                };
+                let mut rust_struct = extract_struct(
+                    &struct_item.ident,
+                    &struct_item.vis,
+                    &struct_item.generics,
+                    &struct_item.fields,
+                    kind,
                 );
-                rust_struct.add_fields(fields);
+                rust_struct.set_span(item_span(struct_item));
+                rust_struct.set_file_path(self.file_path().clone());
+                rust_struct
+                    .set_attributes(extract_attributes(&struct_item.attrs));
+                self.structs.push(rust_struct);
+            }
+            Item::Union(union_item) => {
+                let mut rust_struct = extract_struct(
+                    &union_item.ident,
+                    &union_item.vis,
+                    &union_item.generics,
+                    &syn::Fields::Named(union_item.fields.clone()),
+                    StructKind::Union,
+                );
+                rust_struct.set_span(item_span(union_item));
+                rust_struct.set_file_path(self.file_path().clone());
+                rust_struct
+                    .set_attributes(extract_attributes(&union_item.attrs));
                 self.structs.push(rust_struct);
             }
             Item::Enum(enum_item) => {
@@ -134,77 +221,269 @@ impl<'ast> Visit<'ast> for RustFileVisitor {
                     .variants
                     .iter()
                     .map(|variant| {
-                        let associated_data = variant
-                            .fields
-                            .iter()
-                            .map(|field| {
-                                format!("{}", field.ty.to_token_stream())
-                            })
-                            .collect();
-                        (variant.ident.to_string(), associated_data)
+                        let kind = match variant.fields {
+                            syn::Fields::Named(_) => StructKind::Named,
+                            syn::Fields::Unnamed(_) => StructKind::Tuple,
+                            syn::Fields::Unit => StructKind::Unit,
+                        };
+                        (
+                            variant.ident.to_string(),
+                            kind,
+                            fields_strs(&variant.fields),
+                        )
                     })
                     .collect::<Vec<_>>();
-                let rust_enum = RustEnum::new_with_data(
+                let mut rust_enum = RustEnum::new_with_data(
                     enum_item.ident.to_string(),
                     visibility_to_local_version(&enum_item.vis),
                     variants,
                 );
+                let (generics, where_clause) =
+                    generics_strs(&enum_item.generics);
+                rust_enum.set_generics(generics, where_clause);
+                rust_enum.set_span(item_span(enum_item));
+                rust_enum.set_file_path(self.file_path().clone());
+                rust_enum.set_attributes(extract_attributes(&enum_item.attrs));
                 self.enums.push(rust_enum);
             }
             Item::Trait(trait_item) => {
+                let trait_name = trait_item.ident.to_string();
+                let trait_header =
+                    block_header(Item::Trait(trait_item.clone()));
+                let trait_span = item_span(trait_item);
                 let trait_methods = trait_item
                     .items
                     .iter()
                     .filter_map(|item| {
                         if let TraitItem::Fn(func) = item {
-                            Some(extract_function(
+                            let mut method = extract_function(
                                 &func.sig,
                                 None,
                                 Some(self.file_path().clone()),
                                 func.default.clone().map(Box::new),
-                            ))
+                                item_span(func),
+                                &func.attrs,
+                            );
+                            method.qualify_id(&trait_name);
+                            method.set_impl_block(
+                                trait_header.clone(),
+                                trait_span,
+                            );
+                            Some(method)
                         } else {
                             None
                         }
                     })
                     .collect::<Vec<_>>();
-                let rust_trait = RustTrait::new_with_data(
-                    trait_item.ident.to_string(),
+                let mut rust_trait = RustTrait::new_with_data(
+                    trait_name,
                     visibility_to_local_version(&trait_item.vis),
                     trait_methods,
                 );
+                let (generics, where_clause) =
+                    generics_strs(&trait_item.generics);
+                rust_trait.set_generics(generics, where_clause);
+                if !trait_item.supertraits.is_empty() {
+                    rust_trait.set_supertraits(format!(
+                        ": {}",
+                        trait_item.supertraits.to_token_stream()
+                    ));
+                }
+                rust_trait.set_span(item_span(trait_item));
+                rust_trait.set_file_path(self.file_path().clone());
+                rust_trait
+                    .set_attributes(extract_attributes(&trait_item.attrs));
                 self.traits.push(rust_trait);
             }
+            Item::Const(const_item) => {
+                let mut rust_const = RustConst::new_with_data(
+                    const_item.ident.to_string(),
+                    visibility_to_local_version(&const_item.vis),
+                    const_item.ty.to_token_stream().to_string(),
+                    const_item.expr.to_token_stream().to_string(),
+                );
+                rust_const.set_span(item_span(const_item));
+                rust_const.set_file_path(self.file_path().clone());
+                rust_const
+                    .set_attributes(extract_attributes(&const_item.attrs));
+                self.consts.push(rust_const);
+            }
+            Item::Static(static_item) => {
+                let mut rust_static = RustStatic::new_with_data(
+                    static_item.ident.to_string(),
+                    visibility_to_local_version(&static_item.vis),
+                    matches!(
+                        static_item.mutability,
+                        syn::StaticMutability::Mut(_)
+                    ),
+                    static_item.ty.to_token_stream().to_string(),
+                    static_item.expr.to_token_stream().to_string(),
+                );
+                rust_static.set_span(item_span(static_item));
+                rust_static.set_file_path(self.file_path().clone());
+                rust_static
+                    .set_attributes(extract_attributes(&static_item.attrs));
+                self.statics.push(rust_static);
+            }
+            Item::Type(type_item) => {
+                let mut rust_type_alias = RustTypeAlias::new_with_data(
+                    type_item.ident.to_string(),
+                    visibility_to_local_version(&type_item.vis),
+                    type_item.generics.to_token_stream().to_string(),
+                    type_item.ty.to_token_stream().to_string(),
+                );
+                rust_type_alias.set_span(item_span(type_item));
+                rust_type_alias.set_file_path(self.file_path().clone());
+                rust_type_alias
+                    .set_attributes(extract_attributes(&type_item.attrs));
+                self.type_aliases.push(rust_type_alias);
+            }
+            Item::Use(use_item) => {
+                self.use_declarations
+                    .extend(UseDeclaration::from_item(use_item));
+            }
+            Item::Mod(mod_item) => {
+                let path =
+                    mod_item.attrs.iter().find_map(|attr| match &attr.meta {
+                        syn::Meta::NameValue(name_value)
+                            if name_value.path.is_ident("path") =>
+                        {
+                            match &name_value.value {
+                                syn::Expr::Lit(syn::ExprLit {
+                                    lit: syn::Lit::Str(lit),
+                                    ..
+                                }) => Some(lit.value()),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    });
+                match &mod_item.content {
+                    Some((_, items)) => {
+                        let mut module_visitor =
+                            RustFileVisitor::new(self.file_path.clone());
+                        module_visitor.inline_path = self.inline_path.clone();
+                        module_visitor
+                            .inline_path
+                            .push(mod_item.ident.to_string());
+                        for item in items {
+                            module_visitor.visit_item(item);
+                        }
+                        self.inline_modules.push(module_visitor);
+                    }
+                    None => self.mod_declarations.push(ModDeclaration {
+                        name: mod_item.ident.to_string(),
+                        path,
+                    }),
+                }
+            }
+            Item::Macro(macro_item) => {
+                // only macro_rules! definitions, not macro invocations
+                if let Some(ident) = &macro_item.ident {
+                    let is_exported = macro_item
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("macro_export"));
+                    let mut rust_macro = RustMacro::new_with_data(
+                        ident.to_string(),
+                        if is_exported {
+                            Visibility::Public
+                        } else {
+                            Visibility::Inherited
+                        },
+                        macro_item.mac.tokens.to_string(),
+                    );
+                    rust_macro.set_span(item_span(macro_item));
+                    rust_macro.set_file_path(self.file_path().clone());
+                    rust_macro
+                        .set_attributes(extract_attributes(&macro_item.attrs));
+                    self.macros.push(rust_macro);
+                }
+            }
             _ => {}
         }
     }
 
     fn visit_item_impl(&mut self, impl_item: &'ast syn::ItemImpl) {
         let for_type = format!("{}", impl_item.self_ty.to_token_stream());
+        let impl_header = block_header(Item::Impl(impl_item.clone()));
+        let impl_span = item_span(impl_item);
 
         let mut functions = Vec::new();
         for item in &impl_item.items {
             if let ImplItem::Fn(func) = item {
-                let rust_function = extract_function(
+                let mut rust_function = extract_function(
                     &func.sig,
                     Some(&func.vis),
                     Some(self.file_path().clone()),
                     Some(Box::new(func.block.clone())),
+                    item_span(func),
+                    &func.attrs,
                 );
-                functions.push(rust_function.clone());
+                rust_function.set_impl_block(impl_header.clone(), impl_span);
+                functions.push(rust_function);
             }
         }
 
-        let rust_impl = RustImpl::new_with_data(for_type, functions);
+        let for_trait = impl_item
+            .trait_
+            .as_ref()
+            .map(|(_, path, _)| path.to_token_stream().to_string());
+        let rust_impl = RustImpl::new_with_data(for_type, for_trait, functions);
         self.impls.push(rust_impl);
     }
 }
 
+fn extract_struct(
+    ident: &syn::Ident,
+    vis: &syn::Visibility,
+    generics: &syn::Generics,
+    fields: &syn::Fields,
+    kind: StructKind,
+) -> RustStruct {
+    let fields = fields_strs(fields);
+
+    let id_kind = match kind {
+        StructKind::Union => "union",
+        _ => "struct",
+    };
+    let mut rust_struct = RustStruct::new(
+        &generate_id(id_kind, &ident.to_string()),
+        visibility_to_local_version(vis),
+        &ident.to_string(),
+        kind,
+    );
+    let (generics, where_clause) = generics_strs(generics);
+    rust_struct.set_generics(generics, where_clause);
+    rust_struct.add_fields(fields);
+    rust_struct
+}
+
+// positional fields of tuple structs and variants are named by their index
+fn fields_strs(fields: &syn::Fields) -> Vec<Field> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            (
+                visibility_to_local_version(&field.vis),
+                field
+                    .ident
+                    .as_ref()
+                    .map_or_else(|| index.to_string(), ToString::to_string),
+                format!("{}", field.ty.to_token_stream()),
+            )
+        })
+        .collect()
+}
+
 fn extract_function(
     sig: &syn::Signature,
     vis: Option<&syn::Visibility>,
     file_path: Option<FilePath>,
     block: Option<Box<syn::Block>>,
+    span: Span,
+    attrs: &[syn::Attribute],
 ) -> RustFunction {
     let inputs_vec = sig
         .inputs
@@ -245,14 +524,124 @@ fn extract_function(
         syn::ReturnType::Type(_, ty) => Some(ty.to_token_stream().to_string()),
     };
 
-    RustFunction::new_with_data(
+    let mut rust_function = RustFunction::new_with_data(
         &sig.ident.to_string(),
         vis.map_or(Visibility::Restricted, visibility_to_local_version),
         inputs_vec,
         output_option,
         file_path,
         block,
-    )
+    );
+    let (generics, where_clause) = generics_strs(&sig.generics);
+    rust_function.set_generics(generics, where_clause);
+    rust_function.set_span(span);
+    rust_function.set_attributes(extract_attributes(attrs));
+    rust_function
+}
+
+// from the visibility (or keyword) up to the closing brace or semicolon,
+// outer attributes and doc comments only count for `outer_start`
+fn item_span(item: &(impl ToTokens + Spanned)) -> Span {
+    let tokens: Vec<TokenTree> = item.to_token_stream().into_iter().collect();
+    // an outer attribute is a `#` followed by a bracketed group
+    let mut start = 0;
+    while let [TokenTree::Punct(punct), TokenTree::Group(group), ..] =
+        &tokens[start..]
+    {
+        if punct.as_char() != '#' || group.delimiter() != Delimiter::Bracket {
+            break;
+        }
+        start += 2;
+    }
+    match (tokens.get(start), tokens.last()) {
+        (Some(first), Some(last)) => {
+            Span::new(first.span().start(), last.span().end())
+                .with_outer_start(tokens[0].span().start())
+        }
+        // nothing but attributes, which is not a valid item
+        _ => {
+            let span = item.span();
+            Span::new(span.start(), span.end())
+        }
+    }
+}
+
+// doc comment lines (`#[doc = "..."]`) and the other outer attributes
+fn extract_attributes(attrs: &[syn::Attribute]) -> Attributes {
+    let mut docs = Vec::new();
+    let mut other_attrs = Vec::new();
+    for attr in attrs {
+        if !matches!(attr.style, syn::AttrStyle::Outer) {
+            continue;
+        }
+        match &attr.meta {
+            syn::Meta::NameValue(syn::MetaNameValue {
+                path,
+                value:
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(doc),
+                        ..
+                    }),
+                ..
+            }) if path.is_ident("doc") => {
+                // a block doc comment may span several lines
+                docs.extend(doc.value().lines().map(ToString::to_string));
+            }
+            _ => other_attrs.push(attribute_str(attr)),
+        }
+    }
+    Attributes::new(docs, other_attrs)
+}
+
+// e.g. `#[derive(Debug, Clone)]`, formatted as by prettyplease
+fn attribute_str(attr: &syn::Attribute) -> String {
+    let item: syn::ItemStruct = syn::parse_quote!(#attr struct Item;);
+    let file = File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![Item::Struct(item)],
+    };
+    prettyplease::unparse(&file)
+        .trim_end()
+        .trim_end_matches("struct Item;")
+        .trim_end()
+        .to_string()
+}
+
+// generic parameters (e.g. `<T: Clone>`) and where clause, empty if absent
+fn generics_strs(generics: &syn::Generics) -> (String, String) {
+    let where_clause = generics
+        .where_clause
+        .as_ref()
+        .map(|where_clause| where_clause.to_token_stream().to_string())
+        .unwrap_or_default();
+    (generics.to_token_stream().to_string(), where_clause)
+}
+
+// header of an impl or trait block without its items, e.g.
+// `impl<'a> Display for Foo<'a>`
+fn block_header(mut item: Item) -> String {
+    match &mut item {
+        Item::Impl(item_impl) => {
+            item_impl.attrs.clear();
+            item_impl.items.clear();
+        }
+        Item::Trait(item_trait) => {
+            item_trait.attrs.clear();
+            item_trait.items.clear();
+        }
+        _ => {}
+    }
+    let file = File {
+        shebang: None,
+        attrs: Vec::new(),
+        items: vec![item],
+    };
+    prettyplease::unparse(&file)
+        .trim_end()
+        .trim_end_matches("{}")
+        .trim_end()
+        .to_string()
 }
 
 fn visibility_to_local_version(vis: &syn::Visibility) -> Visibility {
@@ -262,3 +651,65 @@ fn visibility_to_local_version(vis: &syn::Visibility) -> Visibility {
         syn::Visibility::Inherited => Visibility::Inherited,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{GlobalRegistry, Lookup};
+    use crate::types::RustType;
+
+    // a package with a library and two binaries that all define `run`
+    fn package() -> std::path::PathBuf {
+        let package = std::env::temp_dir()
+            .join(format!("codeclippy-targets-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&package);
+        fs::create_dir_all(package.join("src/bin")).unwrap();
+        fs::write(
+            package.join("Cargo.toml"),
+            "[package]\nname = \"demo-app\"\n",
+        )
+        .unwrap();
+        for file in ["src/lib.rs", "src/main.rs", "src/bin/tool.rs"] {
+            fs::write(package.join(file), "pub fn run() {}\n").unwrap();
+        }
+        package
+    }
+
+    #[test]
+    fn items_of_different_targets_have_distinct_ids() {
+        let package = package();
+        let visitors = RustFileVisitor::read_files(
+            &package,
+            vec!["src/lib.rs", "src/main.rs", "src/bin/tool.rs"],
+        )
+        .unwrap();
+
+        let ids: Vec<&str> = visitors
+            .iter()
+            .flat_map(|visitor| &visitor.functions)
+            .map(|function| function.id())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "fn:demo_app::run",
+                "fn:demo_app@bin::run",
+                "fn:tool@bin::run"
+            ]
+        );
+
+        // none of them is dropped as a duplicate
+        let mut registry = GlobalRegistry::default();
+        for function in visitors.iter().flat_map(|visitor| &visitor.functions) {
+            registry.register_item(
+                RustType::Function(Box::new(function.clone())),
+                None,
+            );
+        }
+        match registry.lookup_name("run") {
+            Lookup::Ambiguous(items) => assert_eq!(items.len(), 3),
+            _ => panic!("expected all three functions"),
+        }
+        fs::remove_dir_all(&package).unwrap();
+    }
+}
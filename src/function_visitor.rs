@@ -1,13 +1,88 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use syn::visit::Visit;
 use syn::ExprPath;
 
 use crate::types::{RustFunction, Visibility};
 
+#[derive(Default)]
 pub struct FunctionCallVisitor {
     pub functions: Vec<RustFunction>,
     pub instantiated_items: HashSet<String>,
+    // type of `self` (and `Self`) within methods, e.g. `TreeBuilder`
+    self_type: Option<String>,
+    // best-effort types of parameters and local variables
+    local_types: HashMap<String, String>,
+}
+
+impl FunctionCallVisitor {
+    pub fn new(self_type: Option<&str>, inputs: &[(String, String)]) -> Self {
+        let mut visitor = FunctionCallVisitor {
+            self_type: self_type.map(ToString::to_string),
+            ..Default::default()
+        };
+        for (name, ty) in inputs {
+            if let Some(type_name) = syn::parse_str::<syn::Type>(ty)
+                .ok()
+                .and_then(|ty| visitor.type_name(&ty))
+            {
+                visitor.local_types.insert(name.clone(), type_name);
+            }
+        }
+        visitor
+    }
+
+    // replace a leading `Self` by the actual type, if known
+    fn path_name(&self, path: &syn::Path) -> String {
+        let path = path_to_string(path);
+        match (&self.self_type, path.strip_prefix("Self")) {
+            (Some(self_type), Some(rest))
+                if rest.is_empty() || rest.starts_with("::") =>
+            {
+                format!("{}{}", self_type, rest)
+            }
+            _ => path,
+        }
+    }
+
+    fn type_name(&self, ty: &syn::Type) -> Option<String> {
+        match ty {
+            syn::Type::Reference(reference) => self.type_name(&reference.elem),
+            syn::Type::Paren(paren) => self.type_name(&paren.elem),
+            syn::Type::Path(type_path) => Some(self.path_name(&type_path.path)),
+            _ => None,
+        }
+    }
+
+    // type of an expression, as far as it can be told without type
+    // inference, e.g. `Foo::new()` or `Foo { .. }` is a `Foo`
+    fn expr_type(&self, expr: &syn::Expr) -> Option<String> {
+        match expr {
+            syn::Expr::Path(expr_path) => {
+                let ident = expr_path.path.get_ident()?;
+                if ident == "self" {
+                    self.self_type.clone()
+                } else {
+                    self.local_types.get(&ident.to_string()).cloned()
+                }
+            }
+            syn::Expr::Call(expr_call) => match &*expr_call.func {
+                syn::Expr::Path(expr_path)
+                    if expr_path.path.segments.len() > 1 =>
+                {
+                    let path = self.path_name(&expr_path.path);
+                    path.rsplit_once("::").map(|(ty, _)| ty.to_string())
+                }
+                _ => None,
+            },
+            syn::Expr::Struct(expr_struct) => {
+                Some(self.path_name(&expr_struct.path))
+            }
+            syn::Expr::Reference(reference) => self.expr_type(&reference.expr),
+            syn::Expr::Paren(paren) => self.expr_type(&paren.expr),
+            _ => None,
+        }
+    }
 }
 
 impl<'ast> Visit<'ast> for FunctionCallVisitor {
@@ -15,7 +90,8 @@ impl<'ast> Visit<'ast> for FunctionCallVisitor {
         if let syn::Expr::Path(expr_path) = &*expr_call.func {
             if let Some(last_segment) = expr_path.path.segments.last() {
                 let function_name = last_segment.ident.to_string();
-                self.instantiated_items.insert(function_name.clone());
+                self.instantiated_items
+                    .insert(self.path_name(&expr_path.path));
                 let called_function =
                     RustFunction::new(Visibility::Restricted, &function_name);
                 self.functions.push(called_function);
@@ -24,19 +100,80 @@ impl<'ast> Visit<'ast> for FunctionCallVisitor {
         syn::visit::visit_expr_call(self, expr_call);
     }
 
-    fn visit_expr_struct(&mut self, expr_struct: &'ast syn::ExprStruct) {
-        let struct_name = expr_struct.path.segments[0].ident.to_string();
-        self.instantiated_items.insert(struct_name.clone());
-        syn::visit::visit_expr_struct(self, expr_struct);
+    fn visit_expr_method_call(
+        &mut self,
+        method_call: &'ast syn::ExprMethodCall,
+    ) {
+        // recorded as `Type::method`, methods on receivers of unknown type
+        // are skipped
+        if let Some(receiver_type) = self.expr_type(&method_call.receiver) {
+            let method_name = method_call.method.to_string();
+            self.instantiated_items
+                .insert(format!("{}::{}", receiver_type, method_name));
+            let called_method =
+                RustFunction::new(Visibility::Restricted, &method_name);
+            self.functions.push(called_method);
+        }
+        syn::visit::visit_expr_method_call(self, method_call);
     }
-}
 
-impl Default for FunctionCallVisitor {
-    fn default() -> Self {
-        FunctionCallVisitor {
-            functions: Vec::new(),
-            instantiated_items: HashSet::new(),
+    fn visit_local(&mut self, local: &'ast syn::Local) {
+        let (pat, ty) = match &local.pat {
+            syn::Pat::Type(pat_type) => {
+                (&*pat_type.pat, self.type_name(&pat_type.ty))
+            }
+            pat => (pat, None),
+        };
+        if let syn::Pat::Ident(pat_ident) = pat {
+            let ty = ty.or_else(|| {
+                local
+                    .init
+                    .as_ref()
+                    .and_then(|init| self.expr_type(&init.expr))
+            });
+            match ty {
+                Some(ty) => {
+                    self.local_types.insert(pat_ident.ident.to_string(), ty);
+                }
+                // shadowed by a variable of unknown type
+                None => {
+                    self.local_types.remove(&pat_ident.ident.to_string());
+                }
+            }
+        }
+        syn::visit::visit_local(self, local);
+    }
+
+    fn visit_expr_path(&mut self, expr_path: &'ast ExprPath) {
+        // unit structs (and tuple struct constructors passed as a value)
+        // are plain paths; lowercase idents are most likely local variables
+        let is_local_variable =
+            expr_path.path.get_ident().is_some_and(|ident| {
+                !ident.to_string().starts_with(char::is_uppercase)
+            });
+        if !is_local_variable {
+            self.instantiated_items
+                .insert(self.path_name(&expr_path.path));
         }
+        syn::visit::visit_expr_path(self, expr_path);
+    }
+
+    fn visit_type_path(&mut self, type_path: &'ast syn::TypePath) {
+        // types used in the body, e.g. `let x: Alias = ..`
+        self.instantiated_items
+            .insert(self.path_name(&type_path.path));
+        syn::visit::visit_type_path(self, type_path);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+        self.instantiated_items.insert(path_to_string(&mac.path));
+        syn::visit::visit_macro(self, mac);
+    }
+
+    fn visit_expr_struct(&mut self, expr_struct: &'ast syn::ExprStruct) {
+        self.instantiated_items
+            .insert(self.path_name(&expr_struct.path));
+        syn::visit::visit_expr_struct(self, expr_struct);
     }
 }
 
@@ -54,9 +191,32 @@ impl ItemNameVisitor {
 
 impl<'ast> Visit<'ast> for ItemNameVisitor {
     fn visit_expr_path(&mut self, node: &'ast ExprPath) {
-        if let Some(ident) = node.path.get_ident() {
-            self.item_names.insert(ident.to_string());
-        }
+        self.item_names.insert(path_to_string(&node.path));
         syn::visit::visit_expr_path(self, node);
     }
+
+    fn visit_type_path(&mut self, node: &'ast syn::TypePath) {
+        self.item_names.insert(path_to_string(&node.path));
+        syn::visit::visit_type_path(self, node);
+    }
+
+    fn visit_macro(&mut self, node: &'ast syn::Macro) {
+        self.item_names.insert(path_to_string(&node.path));
+        syn::visit::visit_macro(self, node);
+    }
+}
+
+/// Path as written, without generic arguments, e.g. `super::Foo::new`.
+pub fn path_to_string(path: &syn::Path) -> String {
+    let segments = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::");
+    if path.leading_colon.is_some() {
+        format!("::{}", segments)
+    } else {
+        segments
+    }
 }
@@ -1,8 +1,27 @@
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
+use crate::types::Span;
 
-pub fn generate_id(name: &str) -> String {
-    let mut hasher = DefaultHasher::new();
-    name.hash(&mut hasher);
-    format!("{:x}", hasher.finish())
+/// Identity of an item: its kind followed by its path, e.g.
+/// `fn:codeclippy::map::list_map`. The path starts with the crate of the
+/// item's target, so a binary's items differ from those of its library, e.g.
+/// `fn:codeclippy@bin::main`. Items are created with a path relative to
+/// their module, which is prefixed later on via `qualify_id`.
+pub fn generate_id(kind: &str, path: &str) -> String {
+    format!("{}:{}", kind, path)
+}
+
+/// Prefix the path of `id` with `scope`, e.g. a module or an impl target.
+pub fn qualify_id(id: &str, scope: &str) -> String {
+    match id.split_once(':') {
+        Some((kind, path)) => format!("{}:{}::{}", kind, scope, path),
+        None => format!("{}::{}", scope, id),
+    }
+}
+
+/// Location of an item for `@` headers, e.g. `src/map.rs:24`, or just the
+/// file if the span is unknown.
+pub fn source_location(file: &str, span: Option<Span>) -> String {
+    match span {
+        Some(span) => format!("{}:{}", file, span.start.line),
+        None => file.to_string(),
+    }
 }
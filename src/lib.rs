@@ -3,8 +3,12 @@ pub(crate) mod function_visitor;
 pub(crate) mod helpers;
 pub(crate) mod localfs;
 pub(crate) mod map;
+pub(crate) mod modules;
+pub(crate) mod output;
 pub(crate) mod print_config;
+pub(crate) mod query;
 pub(crate) mod registry;
+pub(crate) mod tokenizer;
 pub(crate) mod tree;
 pub(crate) mod types;
 pub(crate) mod writers;
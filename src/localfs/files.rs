@@ -1,36 +1,253 @@
-use std::fs;
 use std::path::Path;
 
-pub fn map_files_in_directory(
-    directory: &Path,
+use ignore::overrides::{Override, OverrideBuilder};
+use ignore::{DirEntry, WalkBuilder};
+
+/// Which files `map_files_in_directory` lists. `.gitignore` and `.ignore`
+/// files, hidden files and build directories are always respected, the
+/// globs are matched against paths relative to the listed directory.
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
     maxdepth: Option<usize>,
-) -> Vec<String> {
-    map_files_in_directory_recursive(directory, directory, maxdepth)
+    include: Vec<String>,
+    exclude: Vec<String>,
 }
 
-fn map_files_in_directory_recursive(
-    base_directory: &Path,
+impl FileFilter {
+    /// Fails on an invalid glob. Without `include` globs, every file is
+    /// included. Exclusion wins over inclusion.
+    pub fn new(
+        maxdepth: Option<usize>,
+        include: Vec<String>,
+        exclude: Vec<String>,
+    ) -> Result<Self, ignore::Error> {
+        let filter = FileFilter {
+            maxdepth,
+            include,
+            exclude,
+        };
+        filter.include_globs(Path::new("."))?;
+        filter.exclude_globs(Path::new("."))?;
+        Ok(filter)
+    }
+
+    fn include_globs(
+        &self,
+        directory: &Path,
+    ) -> Result<Override, ignore::Error> {
+        build_globs(directory, &self.include, "")
+    }
+
+    // with `!` the globs ignore what they match instead of selecting it
+    fn exclude_globs(
+        &self,
+        directory: &Path,
+    ) -> Result<Override, ignore::Error> {
+        build_globs(directory, &self.exclude, "!")
+    }
+}
+
+/// Relative paths of the `.rs` files in `directory`, sorted. Symbolic
+/// links are followed, a link back to one of its parent directories is
+/// reported and skipped.
+pub fn map_files_in_directory(
     directory: &Path,
-    maxdepth: Option<usize>,
+    filter: &FileFilter,
 ) -> Vec<String> {
-    let mut file_map = Vec::new();
+    let checked = "globs are checked by FileFilter::new";
+    let include = filter.include_globs(directory).expect(checked);
+    // excluded directories are not entered at all, included files are
+    // selected from the rest
+    let exclude = filter.exclude_globs(directory).expect(checked);
+    let walker = WalkBuilder::new(directory)
+        .max_depth(filter.maxdepth)
+        .follow_links(true)
+        // also honour a .gitignore outside of a git repository
+        .require_git(false)
+        .overrides(exclude)
+        .filter_entry(|entry| entry.depth() == 0 || !is_build_directory(entry))
+        // `read_dir` order differs between platforms and runs
+        .sort_by_file_path(|a, b| a.cmp(b))
+        .build();
 
-    if directory.is_dir() && maxdepth.map(|depth| depth > 0).unwrap_or(true) {
-        for entry in fs::read_dir(directory).unwrap() {
-            let entry = entry.unwrap();
-            let path = entry.path();
-            if path.is_dir() {
-                file_map.extend(map_files_in_directory_recursive(
-                    base_directory,
-                    &path,
-                    maxdepth.map(|depth| depth - 1),
-                ));
-            } else if path.extension().unwrap_or_default() == "rs" {
-                let relative_path =
-                    path.strip_prefix(base_directory).unwrap_or(&path);
-                file_map.push(relative_path.display().to_string());
+    let mut file_map = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Skipped: {}", e);
+                continue;
             }
+        };
+        let path = entry.path();
+        let is_included =
+            include.is_empty() || include.matched(path, false).is_whitelist();
+        if path.is_file()
+            && path.extension().unwrap_or_default() == "rs"
+            && is_included
+        {
+            let relative_path = path.strip_prefix(directory).unwrap_or(path);
+            file_map.push(relative_path.display().to_string());
         }
     }
     file_map
 }
+
+fn build_globs(
+    directory: &Path,
+    globs: &[String],
+    prefix: &str,
+) -> Result<Override, ignore::Error> {
+    let mut builder = OverrideBuilder::new(directory);
+    for glob in globs {
+        builder.add(&format!("{}{}", prefix, glob))?;
+    }
+    builder.build()
+}
+
+// Cargo marks its target directory, including any `OUT_DIR`, with a
+// CACHEDIR.TAG, which is missing until the first build completes
+fn is_build_directory(entry: &DirEntry) -> bool {
+    let path = entry.path();
+    if !path.is_dir() {
+        return false;
+    }
+    let is_target = entry.file_name() == "target"
+        && path.with_file_name("Cargo.toml").is_file();
+    is_target || path.join("CACHEDIR.TAG").is_file()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn project(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!(
+            "codeclippy-files-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&directory);
+        for file in [
+            "Cargo.toml",
+            "build.rs",
+            "src/lib.rs",
+            "src/main.rs",
+            "src/util/mod.rs",
+            "src/util/tests.rs",
+            "tests/api.rs",
+            "target/debug/build/out.rs",
+            "ignored/skip.rs",
+            "notes.txt",
+        ] {
+            let path = directory.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        fs::write(directory.join(".gitignore"), "ignored/\n").unwrap();
+        directory
+    }
+
+    fn globs(globs: &[&str]) -> Vec<String> {
+        globs.iter().map(|glob| glob.to_string()).collect()
+    }
+
+    #[test]
+    fn filters_by_include_and_exclude_globs() {
+        let directory = project("globs");
+        let cases: [(&[&str], &[&str], &[&str]); 9] = [
+            (
+                &[],
+                &[],
+                &[
+                    "build.rs",
+                    "src/lib.rs",
+                    "src/main.rs",
+                    "src/util/mod.rs",
+                    "src/util/tests.rs",
+                    "tests/api.rs",
+                ],
+            ),
+            (
+                &["src/**"],
+                &[],
+                &[
+                    "src/lib.rs",
+                    "src/main.rs",
+                    "src/util/mod.rs",
+                    "src/util/tests.rs",
+                ],
+            ),
+            (&["src/*.rs"], &[], &["src/lib.rs", "src/main.rs"]),
+            (
+                &["mod.rs", "tests/**"],
+                &[],
+                &["src/util/mod.rs", "tests/api.rs"],
+            ),
+            (&["*.txt"], &[], &[]),
+            (
+                &[],
+                &["util", "tests", "build.rs"],
+                &["src/lib.rs", "src/main.rs"],
+            ),
+            (
+                &[],
+                &["tests.rs"],
+                &[
+                    "build.rs",
+                    "src/lib.rs",
+                    "src/main.rs",
+                    "src/util/mod.rs",
+                    "tests/api.rs",
+                ],
+            ),
+            // exclusion wins over inclusion
+            (&["src/**"], &["util", "main.rs"], &["src/lib.rs"]),
+            (&["src/util/**"], &["src"], &[]),
+        ];
+        for (include, exclude, expected) in cases {
+            let filter =
+                FileFilter::new(None, globs(include), globs(exclude)).unwrap();
+            let files = map_files_in_directory(&directory, &filter);
+            assert_eq!(files, expected, "{:?} {:?}", include, exclude);
+        }
+    }
+
+    #[test]
+    fn limits_the_depth() {
+        let directory = project("depth");
+        let cases: [(usize, &[&str]); 3] = [
+            (1, &["build.rs"]),
+            (
+                2,
+                &["build.rs", "src/lib.rs", "src/main.rs", "tests/api.rs"],
+            ),
+            (
+                3,
+                &[
+                    "build.rs",
+                    "src/lib.rs",
+                    "src/main.rs",
+                    "src/util/mod.rs",
+                    "src/util/tests.rs",
+                    "tests/api.rs",
+                ],
+            ),
+        ];
+        for (maxdepth, expected) in cases {
+            let filter =
+                FileFilter::new(Some(maxdepth), vec![], vec![]).unwrap();
+            let files = map_files_in_directory(&directory, &filter);
+            assert_eq!(files, expected, "{}", maxdepth);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_globs() {
+        assert!(FileFilter::new(None, globs(&["src/[a"]), vec![]).is_err());
+        assert!(FileFilter::new(None, vec![], globs(&["{a,b"])).is_err());
+        assert!(FileFilter::new(None, globs(&["src/**"]), vec![]).is_ok());
+    }
+}
@@ -2,4 +2,4 @@ mod file_path;
 mod files;
 
 pub use file_path::FilePath;
-pub use files::map_files_in_directory;
+pub use files::{map_files_in_directory, FileFilter};
@@ -1,21 +1,47 @@
 use std::path::Path;
+use std::ptr;
+use std::rc::Rc;
 
 use crate::file_visitor::RustFileVisitor;
-use crate::localfs::map_files_in_directory;
-use crate::tree::TreeBuilder;
-use crate::writers::{BufferedWriter, ClippyWriter};
+use crate::localfs::{map_files_in_directory, FileFilter};
+use crate::output::{
+    fit_budget, json_document, print_markdown, print_xml, OutputFormat,
+};
+use crate::query::Query;
+use crate::tokenizer::{ApproximateTokenizer, Tokenizer};
+use crate::tree::{Depth, Order, RootNode, TreeBuilder};
+use crate::types::CodeStyle;
+use crate::writers::ClippyWriter;
+
+/// Output options of `list_map`, as passed on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    pub silence_query: bool,
+    pub show_dependencies: bool,
+    pub show_dependents: bool,
+    pub show_ids: bool,
+    pub show_owners: bool,
+    pub code_style: CodeStyle,
+    pub files: FileFilter,
+    pub depth: Depth,
+    pub order: Order,
+    pub format: OutputFormat,
+    /// Maximum number of tokens of the output, see `fit_budget`.
+    pub budget: Option<usize>,
+    /// Report the tokens of each item on stderr.
+    pub count_tokens: bool,
+    /// Approximate counting if not set.
+    pub tokenizer: Option<Rc<dyn Tokenizer>>,
+}
 
 pub fn list_map(
     directory: &str,
-    filter: Option<&str>,
+    filter: Option<&Query>,
     writer: &mut Box<dyn ClippyWriter>,
-    silence_query: bool,
-    show_dependencies: bool,
-    show_dependents: bool,
-    maxdepth: Option<usize>,
+    options: &ListOptions,
 ) {
     let base_directory = Path::new(directory);
-    let file_map = map_files_in_directory(base_directory, maxdepth);
+    let file_map = map_files_in_directory(base_directory, &options.files);
     // collect relative paths from base_directory
     let file_paths: Vec<&str> = file_map.iter().map(AsRef::as_ref).collect();
 
@@ -24,46 +50,124 @@ pub fn list_map(
         RustFileVisitor::read_files(base_directory, file_paths).unwrap();
 
     let use_full_path = if let Some(filter) = filter {
-        let first_element = filter.split("::").next().unwrap_or("");
+        let first_element = filter.as_str().split("::").next().unwrap_or("");
         let real_path = base_directory.join(first_element);
         real_path.exists()
     } else {
         false
     };
 
-    let link_dependencies = show_dependencies && filter.is_some();
-    let link_dependents = show_dependents && filter.is_some();
+    let link_dependencies = options.show_dependencies && filter.is_some();
+    let link_dependents = options.show_dependents && filter.is_some();
 
-    let mut builder = TreeBuilder::new(visitors, use_full_path);
-    let root_nodes = builder.initialize_root_nodes(
+    let mut builder =
+        TreeBuilder::new(visitors, use_full_path).with_depth(options.depth);
+    let mut root_nodes = builder.initialize_root_nodes(
         filter,
         link_dependencies,
         link_dependents,
     );
+    for root in &mut root_nodes {
+        root.sort_children(options.order);
+        root.dependencies_mut().sort(options.order);
+        root.dependents_mut().sort(options.order);
+    }
 
-    for root in &root_nodes {
-        if !silence_query {
-            let mut buffered_writer: Box<dyn ClippyWriter> =
-                Box::new(BufferedWriter::new());
+    if options.budget.is_some() || options.count_tokens {
+        let approximate = ApproximateTokenizer::new();
+        let tokenizer = options.tokenizer.as_deref().unwrap_or(&approximate);
+        let report = fit_budget(
+            &mut root_nodes,
+            filter,
+            use_full_path,
+            options,
+            tokenizer,
+        );
+        if options.count_tokens {
+            eprint!("{}", report);
+        }
+        if report.exceeds_budget() {
+            eprintln!(
+                "Output of {} tokens exceeds the budget of {}, queried items \
+                 and direct dependencies are always included",
+                report.total(),
+                options.budget.unwrap_or_default()
+            );
+        }
+    }
 
-            root.print(&mut buffered_writer, filter, use_full_path);
+    print_output(&root_nodes, filter, use_full_path, writer, options);
+}
 
-            if let Some(buffered_data) = buffered_writer.get_buffer() {
-                let buffered_str = String::from_utf8_lossy(buffered_data);
-                let _ = writeln!(
+/// The items of `root_nodes` in the format of `options`.
+pub fn print_output(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    writer: &mut Box<dyn ClippyWriter>,
+    options: &ListOptions,
+) {
+    match options.format {
+        OutputFormat::Text => {
+            print_text(root_nodes, filter, use_full_path, writer, options)
+        }
+        OutputFormat::Markdown => {
+            print_markdown(root_nodes, filter, use_full_path, writer, options)
+        }
+        OutputFormat::Xml => {
+            print_xml(root_nodes, filter, use_full_path, writer, options)
+        }
+        OutputFormat::Json => {
+            let document =
+                json_document(root_nodes, filter, use_full_path, options);
+            let _ = writeln!(writer, "{:#}", document);
+        }
+    }
+}
+
+fn print_text(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    writer: &mut Box<dyn ClippyWriter>,
+    options: &ListOptions,
+) {
+    // each root node is followed by the dependencies and dependents listed
+    // for it
+    let mut dependencies = RootNode::unique_dependencies(root_nodes)
+        .filter(|_| options.show_dependencies)
+        .peekable();
+    let mut dependents = RootNode::unique_dependents(root_nodes)
+        .filter(|_| options.show_dependents)
+        .peekable();
+    for root in root_nodes {
+        if !options.silence_query {
+            // each item is printed with its own `@file:line:` header
+            if !root.matching_nodes(filter, use_full_path).is_empty() {
+                root.print(
                     writer,
-                    "@{}:\n{}",
-                    root.file_path().relative_path(),
-                    buffered_str
+                    filter,
+                    use_full_path,
+                    options.show_ids,
+                    options.code_style,
                 );
+                let _ = writeln!(writer);
             }
         }
-        if show_dependencies && root.dependencies().len() > 0 {
-            root.dependencies().print(writer);
+        while let Some((_, dependency)) =
+            dependencies.next_if(|(listed_for, _)| ptr::eq(*listed_for, root))
+        {
+            dependency.print(writer, options.show_ids, options.code_style);
         }
-
-        if show_dependents && root.dependents().len() > 0 {
-            root.dependents().print(writer);
+        while let Some((_, dependent)) =
+            dependents.next_if(|(listed_for, _)| ptr::eq(*listed_for, root))
+        {
+            dependent.print(
+                writer,
+                options.show_ids,
+                options.show_owners,
+                options.code_style,
+            );
         }
     }
 }
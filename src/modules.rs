@@ -0,0 +1,612 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::file_visitor::RustFileVisitor;
+
+/// Kind of a Cargo target, see `CrateTarget`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+}
+
+impl TargetKind {
+    fn name(self) -> &'static str {
+        match self {
+            TargetKind::Lib => "lib",
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+            TargetKind::Test => "test",
+            TargetKind::Bench => "bench",
+        }
+    }
+}
+
+/// A crate of a package, found the way Cargo discovers targets: the
+/// library (`src/lib.rs`), a binary (`src/main.rs`, `src/bin/*.rs`), an
+/// example, test or bench.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrateTarget {
+    pub kind: TargetKind,
+    pub name: String,
+    // root file, relative to the listed directory
+    root: PathBuf,
+    // directory of the Cargo.toml, None without one
+    package: Option<PathBuf>,
+}
+
+impl CrateTarget {
+    /// Name of the crate in item ids and module paths. Other targets than
+    /// the library are suffixed with their kind, e.g. `codeclippy@bin`, as
+    /// a binary is usually named after the library.
+    pub fn crate_name(&self) -> String {
+        match self.kind {
+            TargetKind::Lib => self.name.clone(),
+            kind => format!("{}@{}", self.name, kind.name()),
+        }
+    }
+}
+
+/// An out-of-line module declaration, e.g. `mod builder;` or
+/// `#[path = "other.rs"] mod builder;`.
+#[derive(Debug, Clone)]
+pub struct ModDeclaration {
+    pub name: String,
+    pub path: Option<String>,
+}
+
+/// A single name brought into scope by a `use` item, with its path as
+/// written. `use a::{b as c, d::*}` results in two declarations.
+#[derive(Debug, Clone)]
+pub struct UseDeclaration {
+    // local name, None for glob imports
+    pub alias: Option<String>,
+    pub path: Vec<String>,
+}
+
+impl UseDeclaration {
+    pub fn from_item(item_use: &syn::ItemUse) -> Vec<UseDeclaration> {
+        let mut declarations = Vec::new();
+        // `use ::name` always refers to an external crate
+        let prefix = if item_use.leading_colon.is_some() {
+            vec![String::new()]
+        } else {
+            Vec::new()
+        };
+        flatten_use_tree(&item_use.tree, prefix, &mut declarations);
+        declarations
+    }
+}
+
+fn flatten_use_tree(
+    tree: &syn::UseTree,
+    mut prefix: Vec<String>,
+    declarations: &mut Vec<UseDeclaration>,
+) {
+    match tree {
+        syn::UseTree::Path(use_path) => {
+            prefix.push(use_path.ident.to_string());
+            flatten_use_tree(&use_path.tree, prefix, declarations);
+        }
+        syn::UseTree::Name(use_name) => {
+            // `use a::b::{self}` imports `b`
+            if use_name.ident != "self" {
+                prefix.push(use_name.ident.to_string());
+            }
+            if let Some(alias) = prefix.last() {
+                declarations.push(UseDeclaration {
+                    alias: Some(alias.clone()),
+                    path: prefix,
+                });
+            }
+        }
+        syn::UseTree::Rename(use_rename) => {
+            if use_rename.ident != "self" {
+                prefix.push(use_rename.ident.to_string());
+            }
+            declarations.push(UseDeclaration {
+                alias: Some(use_rename.rename.to_string()),
+                path: prefix,
+            });
+        }
+        syn::UseTree::Glob(_) => {
+            declarations.push(UseDeclaration {
+                alias: None,
+                path: prefix,
+            });
+        }
+        syn::UseTree::Group(use_group) => {
+            for tree in &use_group.items {
+                flatten_use_tree(tree, prefix.clone(), declarations);
+            }
+        }
+    }
+}
+
+/// Assign a module path (e.g. `codeclippy::tree::builder`) to every
+/// visitor. The first segment is the crate name of its target, see
+/// `CrateTarget::crate_name`.
+///
+/// Module files are resolved by following `mod` declarations from the root
+/// of each target, the same way rustc does. A file reachable from several
+/// targets belongs to the first, the library before binaries. Files that
+/// can not be reached this way get a module path derived from their
+/// location, in the library of their package if there is one.
+pub fn resolve_module_paths(
+    base_directory: &Path,
+    visitors: &mut [RustFileVisitor],
+) {
+    let base_directory = base_directory
+        .canonicalize()
+        .unwrap_or_else(|_| base_directory.to_path_buf());
+    let known_files: HashSet<PathBuf> = visitors.iter().map(file_of).collect();
+    let packages = package_directories(&base_directory, &known_files);
+    let targets = crate_targets(&base_directory, &packages);
+
+    let mut file_modules: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    // files that resolve nested modules relative to their own directory
+    let mut mod_rs_files: HashSet<PathBuf> = HashSet::new();
+    let mut queue = VecDeque::new();
+    for target in &targets {
+        file_modules.insert(target.root.clone(), vec![target.crate_name()]);
+        mod_rs_files.insert(target.root.clone());
+        queue.push_back(target.root.clone());
+    }
+
+    while let Some(file) = queue.pop_front() {
+        let file_module = file_modules[&file].clone();
+        let is_mod_rs =
+            mod_rs_files.contains(&file) || file.ends_with("mod.rs");
+
+        for visitor in visitors.iter().filter(|v| file_of(v) == file) {
+            for declaration in &visitor.mod_declarations {
+                let candidates = module_file_candidates(
+                    &file,
+                    is_mod_rs,
+                    visitor.inline_path(),
+                    declaration,
+                );
+                let Some(found) = candidates
+                    .into_iter()
+                    .find(|candidate| known_files.contains(candidate))
+                else {
+                    log::debug!(
+                        "Module '{}' declared in {} not found",
+                        declaration.name,
+                        file.display()
+                    );
+                    continue;
+                };
+                if file_modules.contains_key(&found) {
+                    continue;
+                }
+                let mut module_path = file_module.clone();
+                module_path.extend(visitor.inline_path().iter().cloned());
+                module_path.push(declaration.name.clone());
+
+                if declaration.path.is_some() {
+                    mod_rs_files.insert(found.clone());
+                }
+                file_modules.insert(found.clone(), module_path);
+                queue.push_back(found);
+            }
+        }
+    }
+
+    for visitor in visitors.iter_mut() {
+        let file = file_of(visitor);
+        let mut module_path =
+            file_modules.get(&file).cloned().unwrap_or_else(|| {
+                let package = packages.get(&file).cloned().flatten();
+                let target = fallback_target(&targets, &file, package);
+                module_path_from_file(&file, target)
+            });
+        module_path.extend(visitor.inline_path().iter().cloned());
+        visitor.set_module_path(module_path);
+    }
+}
+
+// `name` in the given section (e.g. `[package]`), without a full TOML
+// parser
+fn manifest_name(manifest: &str, section: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in manifest.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_section = line == section;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        if in_section && key.trim() == "name" {
+            return Some(value.trim().trim_matches('"').to_string());
+        }
+    }
+    None
+}
+
+// the directory of the nearest Cargo.toml above each file, which may be
+// above the listed directory, e.g. when listing `src`
+fn package_directories(
+    base_directory: &Path,
+    known_files: &HashSet<PathBuf>,
+) -> HashMap<PathBuf, Option<PathBuf>> {
+    let mut has_manifest: HashMap<PathBuf, bool> = HashMap::new();
+    known_files
+        .iter()
+        .map(|file| {
+            let directory = base_directory.join(file);
+            let package = directory.ancestors().skip(1).find(|directory| {
+                *has_manifest
+                    .entry(directory.to_path_buf())
+                    .or_insert_with(|| directory.join("Cargo.toml").is_file())
+            });
+            (file.clone(), package.map(Path::to_path_buf))
+        })
+        .collect()
+}
+
+// sorted by kind, so the library comes first, then by path
+fn crate_targets(
+    base_directory: &Path,
+    packages: &HashMap<PathBuf, Option<PathBuf>>,
+) -> Vec<CrateTarget> {
+    let mut manifests: HashMap<PathBuf, String> = HashMap::new();
+    let mut targets: Vec<CrateTarget> = packages
+        .iter()
+        .filter_map(|(file, package)| {
+            let Some(package) = package else {
+                // without a Cargo.toml, only a lib.rs or main.rs at the top
+                // is a root
+                let (kind, _) = target_of(file)
+                    .filter(|_| file.components().count() == 1)?;
+                return Some(CrateTarget {
+                    kind,
+                    name: "crate".to_string(),
+                    root: file.clone(),
+                    package: None,
+                });
+            };
+            let package_file = base_directory.join(file);
+            let (kind, name) =
+                target_of(package_file.strip_prefix(package).ok()?)?;
+            let manifest =
+                manifests.entry(package.clone()).or_insert_with(|| {
+                    fs::read_to_string(package.join("Cargo.toml"))
+                        .unwrap_or_default()
+                });
+            let name = match (kind, name) {
+                (_, Some(name)) => name,
+                (TargetKind::Lib, None) => manifest_name(manifest, "[lib]")
+                    .or_else(|| manifest_name(manifest, "[package]"))?,
+                (_, None) => manifest_name(manifest, "[package]")?,
+            };
+            Some(CrateTarget {
+                kind,
+                // crate names can not contain dashes
+                name: name.replace('-', "_"),
+                root: file.clone(),
+                package: Some(package.clone()),
+            })
+        })
+        .collect();
+    targets.sort_by(|a, b| (a.kind, &a.root).cmp(&(b.kind, &b.root)));
+    targets
+}
+
+/// Kind and name of the target `path` (relative to its package) is the
+/// root of, following Cargo's target auto-discovery. The name is None
+/// for targets named after the package.
+fn target_of(path: &Path) -> Option<(TargetKind, Option<String>)> {
+    let components: Vec<&str> = path
+        .components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<_>>()?;
+    let kind_of = |directory: &str| match directory {
+        "examples" => Some(TargetKind::Example),
+        "tests" => Some(TargetKind::Test),
+        "benches" => Some(TargetKind::Bench),
+        _ => None,
+    };
+    let name_of = |file: &str| file.strip_suffix(".rs").map(String::from);
+    match components.as_slice() {
+        ["lib.rs"] | ["src", "lib.rs"] => Some((TargetKind::Lib, None)),
+        ["main.rs"] | ["src", "main.rs"] => Some((TargetKind::Bin, None)),
+        // `src/bin/tool.rs` and `src/bin/tool/main.rs` are the binary
+        // `tool`, and likewise for examples, tests and benches
+        ["src", "bin", file] => Some((TargetKind::Bin, Some(name_of(file)?))),
+        ["src", "bin", name, "main.rs"] => {
+            Some((TargetKind::Bin, Some(name.to_string())))
+        }
+        [directory, file] => Some((kind_of(directory)?, Some(name_of(file)?))),
+        [directory, name, "main.rs"] => {
+            Some((kind_of(directory)?, Some(name.to_string())))
+        }
+        _ => None,
+    }
+}
+
+// for a file no target reaches: the target with the innermost directory
+// containing the file (e.g. `src/bin/tool/` for `src/bin/tool/main.rs`),
+// else the library of the package, or any target
+fn fallback_target<'a>(
+    targets: &'a [CrateTarget],
+    file: &Path,
+    package: Option<PathBuf>,
+) -> Option<&'a CrateTarget> {
+    let in_package: Vec<&CrateTarget> = targets
+        .iter()
+        .filter(|target| target.package == package)
+        .collect();
+    in_package
+        .iter()
+        .filter(|target| {
+            target.root.ends_with("lib.rs") || target.root.ends_with("main.rs")
+        })
+        .filter_map(|target| Some((target.root.parent()?, *target)))
+        .filter(|(directory, _)| file.starts_with(directory))
+        // the first, i.e. the library, of equally deep ones
+        .min_by_key(|(directory, _)| Reverse(directory.components().count()))
+        .map(|(_, target)| target)
+        .or_else(|| in_package.first().copied())
+        .or_else(|| targets.first())
+}
+
+fn file_of(visitor: &RustFileVisitor) -> PathBuf {
+    normalize(Path::new(&visitor.file_path().relative_path()))
+}
+
+fn module_file_candidates(
+    file: &Path,
+    is_mod_rs: bool,
+    inline_path: &[String],
+    declaration: &ModDeclaration,
+) -> Vec<PathBuf> {
+    let directory = file.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    // a #[path] outside of inline modules is relative to the file itself
+    if let (Some(path), true) = (&declaration.path, inline_path.is_empty()) {
+        return vec![normalize(&directory.join(path))];
+    }
+
+    let mut module_directory = directory;
+    if !is_mod_rs {
+        // `foo.rs` keeps its submodules in `foo/`
+        if let Some(stem) = file.file_stem() {
+            module_directory.push(stem);
+        }
+    }
+    for inline_module in inline_path {
+        module_directory.push(inline_module);
+    }
+
+    match &declaration.path {
+        Some(path) => vec![normalize(&module_directory.join(path))],
+        None => vec![
+            normalize(
+                &module_directory.join(format!("{}.rs", declaration.name)),
+            ),
+            normalize(&module_directory.join(&declaration.name).join("mod.rs")),
+        ],
+    }
+}
+
+// the location of `file` relative to the root of `target`, e.g.
+// `src/tree/mod.rs` is `codeclippy::tree` in the library of codeclippy
+fn module_path_from_file(
+    file: &Path,
+    target: Option<&CrateTarget>,
+) -> Vec<String> {
+    let (crate_name, crate_directory) = match target {
+        Some(target) => (
+            target.crate_name(),
+            target.root.parent().unwrap_or(Path::new("")),
+        ),
+        None => ("crate".to_string(), Path::new("")),
+    };
+    let relative = file.strip_prefix(crate_directory).unwrap_or(file);
+    let mut module_path = vec![crate_name];
+    for component in relative.with_extension("").components() {
+        if let Component::Normal(name) = component {
+            module_path.push(name.to_string_lossy().to_string());
+        }
+    }
+    if module_path.len() > 1
+        && ["mod", "lib", "main"]
+            .contains(&module_path.last().unwrap().as_str())
+    {
+        module_path.pop();
+    }
+    module_path
+}
+
+// lexically resolve `.` and `..`, the files do not need to exist
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // module path of each file (and inline module) of a package `demo`
+    fn module_paths(
+        name: &str,
+        files: &[(&str, &str)],
+    ) -> HashMap<String, String> {
+        let package = std::env::temp_dir().join(format!(
+            "codeclippy-modules-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&package);
+        fs::create_dir_all(&package).unwrap();
+        fs::write(package.join("Cargo.toml"), "[package]\nname = \"demo\"\n")
+            .unwrap();
+        for (file, content) in files {
+            let path = package.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        let relative_paths = files.iter().map(|(file, _)| *file).collect();
+        let visitors =
+            RustFileVisitor::read_files(&package, relative_paths).unwrap();
+        fs::remove_dir_all(&package).unwrap();
+        visitors
+            .iter()
+            .map(|visitor| {
+                let location = std::iter::once(file_of(visitor))
+                    .map(|file| file.to_string_lossy().to_string())
+                    .chain(visitor.inline_path().iter().cloned())
+                    .collect::<Vec<_>>()
+                    .join("::");
+                (location, visitor.module_path().join("::"))
+            })
+            .collect()
+    }
+
+    fn assert_paths(
+        paths: &HashMap<String, String>,
+        expected: &[(&str, &str)],
+    ) {
+        for (location, module_path) in expected {
+            assert_eq!(
+                paths.get(*location).map(String::as_str),
+                Some(*module_path),
+                "module path of {}",
+                location
+            );
+        }
+    }
+
+    #[test]
+    fn resolves_foo_rs_and_foo_mod_rs() {
+        let paths = module_paths(
+            "files",
+            &[
+                ("src/lib.rs", "mod a; mod b;"),
+                ("src/a.rs", "mod c;"),
+                ("src/a/c.rs", ""),
+                ("src/b/mod.rs", "mod d;"),
+                ("src/b/d.rs", ""),
+            ],
+        );
+        assert_paths(
+            &paths,
+            &[
+                ("src/lib.rs", "demo"),
+                ("src/a.rs", "demo::a"),
+                ("src/a/c.rs", "demo::a::c"),
+                ("src/b/mod.rs", "demo::b"),
+                ("src/b/d.rs", "demo::b::d"),
+            ],
+        );
+    }
+
+    #[test]
+    fn resolves_path_attributes() {
+        let paths = module_paths(
+            "path",
+            &[
+                (
+                    "src/lib.rs",
+                    r#"
+                    #[path = "other/x.rs"]
+                    mod x;
+                    mod inner {
+                        #[path = "y.rs"]
+                        mod y;
+                    }
+                    "#,
+                ),
+                // a file included with #[path] is treated like a mod.rs
+                ("src/other/x.rs", "mod z;"),
+                ("src/other/z.rs", ""),
+                // inside an inline module, the path is relative to its
+                // directory
+                ("src/inner/y.rs", ""),
+            ],
+        );
+        assert_paths(
+            &paths,
+            &[
+                ("src/other/x.rs", "demo::x"),
+                ("src/other/z.rs", "demo::x::z"),
+                ("src/lib.rs::inner", "demo::inner"),
+                ("src/inner/y.rs", "demo::inner::y"),
+            ],
+        );
+    }
+
+    #[test]
+    fn unreachable_files_are_placed_by_location() {
+        let paths = module_paths(
+            "unreachable",
+            &[
+                ("src/lib.rs", ""),
+                ("src/main.rs", ""),
+                ("src/orphan/thing.rs", ""),
+                ("src/bin/tool/main.rs", ""),
+                ("src/bin/tool/orphan.rs", ""),
+                ("examples/walk.rs", ""),
+            ],
+        );
+        assert_paths(
+            &paths,
+            &[
+                ("src/orphan/thing.rs", "demo::orphan::thing"),
+                ("src/main.rs", "demo@bin"),
+                ("src/bin/tool/main.rs", "tool@bin"),
+                // files no target reaches belong to the innermost target
+                ("src/bin/tool/orphan.rs", "tool@bin::orphan"),
+                ("examples/walk.rs", "walk@example"),
+            ],
+        );
+    }
+
+    #[test]
+    fn finds_targets_like_cargo() {
+        let cases = [
+            ("src/lib.rs", Some((TargetKind::Lib, None))),
+            ("src/main.rs", Some((TargetKind::Bin, None))),
+            ("src/bin/tool.rs", Some((TargetKind::Bin, Some("tool")))),
+            (
+                "src/bin/tool/main.rs",
+                Some((TargetKind::Bin, Some("tool"))),
+            ),
+            (
+                "examples/walk.rs",
+                Some((TargetKind::Example, Some("walk"))),
+            ),
+            ("tests/cli/main.rs", Some((TargetKind::Test, Some("cli")))),
+            ("benches/parse.rs", Some((TargetKind::Bench, Some("parse")))),
+            ("src/map.rs", None),
+            ("src/bin/tool/cmd.rs", None),
+            ("bin/tool.rs", None),
+            ("tests/common/mod.rs", None),
+        ];
+        for (path, expected) in cases {
+            let expected =
+                expected.map(|(kind, name)| (kind, name.map(String::from)));
+            assert_eq!(target_of(Path::new(path)), expected, "{}", path);
+        }
+    }
+}
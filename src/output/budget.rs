@@ -0,0 +1,370 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::map::{print_output, ListOptions};
+use crate::query::Query;
+use crate::tokenizer::Tokenizer;
+use crate::tree::RootNode;
+
+use super::{item_output, render, ListedItem};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Section {
+    Query,
+    Dependency,
+    Dependent,
+}
+
+impl Section {
+    fn name(self) -> &'static str {
+        match self {
+            Section::Query => "query",
+            Section::Dependency => "dependency",
+            Section::Dependent => "dependent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fate {
+    Full,
+    Skeleton,
+    Dropped,
+}
+
+struct ItemTokens {
+    id: String,
+    section: Section,
+    // 1 for direct dependencies and dependents, 2 for theirs and so on
+    level: usize,
+    full: usize,
+    skeleton: usize,
+    fate: Fate,
+}
+
+impl ItemTokens {
+    fn tokens(&self) -> usize {
+        match self.fate {
+            Fate::Full => self.full,
+            Fate::Skeleton => self.skeleton,
+            Fate::Dropped => 0,
+        }
+    }
+}
+
+/// Tokens per item in the output of `ls`, and the items reduced to a
+/// skeleton or dropped to fit the budget.
+pub struct TokenReport {
+    items: Vec<ItemTokens>,
+    // headings and sections around the items, e.g. `<dependencies>`
+    overhead: usize,
+    budget: Option<usize>,
+}
+
+impl TokenReport {
+    pub fn total(&self) -> usize {
+        self.overhead + self.items.iter().map(ItemTokens::tokens).sum::<usize>()
+    }
+
+    pub fn exceeds_budget(&self) -> bool {
+        self.budget.is_some_and(|budget| self.total() > budget)
+    }
+}
+
+impl fmt::Display for TokenReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>7}  {:<10}  item", "tokens", "section")?;
+        for item in &self.items {
+            let note = match item.fate {
+                Fate::Full => String::new(),
+                Fate::Skeleton => format!(" (skeleton, {} in full)", item.full),
+                Fate::Dropped => format!(" (dropped, {} in full)", item.full),
+            };
+            writeln!(
+                f,
+                "{:>7}  {:<10}  {}{}",
+                item.tokens(),
+                item.section.name(),
+                item.id,
+                note
+            )?;
+        }
+        writeln!(f, "{:>7}  {:<10}  headings", self.overhead, "")?;
+        write!(f, "{:>7}  total", self.total())?;
+        match self.budget {
+            Some(budget) => writeln!(f, " (budget {})", budget),
+            None => writeln!(f),
+        }
+    }
+}
+
+/// Count the tokens of each item `list_map` would print and, with a
+/// budget in `options`, reduce the output until it fits: dependents are
+/// reduced to skeletons first (the most indirect ones first), then
+/// transitive dependencies. If that is not enough, they are dropped in the
+/// same order. Queried items and direct dependencies are always kept.
+///
+/// Items are counted as written in the format of `options`, with their
+/// headers or wrappers, the headings and sections around them are counted
+/// as a whole.
+pub fn fit_budget(
+    root_nodes: &mut [RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    options: &ListOptions,
+    tokenizer: &dyn Tokenizer,
+) -> TokenReport {
+    let mut items =
+        count_items(root_nodes, filter, use_full_path, options, tokenizer);
+    // whatever the complete output has on top of its items
+    let output = render(|writer| {
+        print_output(root_nodes, filter, use_full_path, writer, options)
+    });
+    let items_total: usize = items.iter().map(|item| item.full).sum();
+    let overhead = tokenizer.count(&output).saturating_sub(items_total);
+
+    if let Some(budget) = options.budget {
+        degrade(&mut items, budget.saturating_sub(overhead));
+    }
+
+    for item in &items {
+        apply_fate(root_nodes, item);
+    }
+    TokenReport {
+        items,
+        overhead,
+        budget: options.budget,
+    }
+}
+
+// dependents, the most indirect first, then transitive dependencies are
+// reduced to skeletons, then dropped, until the total fits
+fn degrade(items: &mut [ItemTokens], budget: usize) {
+    let mut candidates: Vec<usize> = (0..items.len())
+        .filter(|&index| match items[index].section {
+            Section::Query => false,
+            Section::Dependency => items[index].level > 1,
+            Section::Dependent => true,
+        })
+        .collect();
+    candidates.sort_by_key(|&index| {
+        let item = &items[index];
+        let section_order = match item.section {
+            Section::Dependent => 0,
+            _ => 1,
+        };
+        (section_order, Reverse(item.level), item.id.clone())
+    });
+
+    let mut total: usize = items.iter().map(ItemTokens::tokens).sum();
+    for fate in [Fate::Skeleton, Fate::Dropped] {
+        for &index in &candidates {
+            if total <= budget {
+                break;
+            }
+            let item = &mut items[index];
+            total -= item.tokens();
+            item.fate = fate;
+            total += item.tokens();
+        }
+    }
+}
+
+fn count_items(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    options: &ListOptions,
+    tokenizer: &dyn Tokenizer,
+) -> Vec<ItemTokens> {
+    let skeleton_options = ListOptions {
+        code_style: options.code_style.as_skeleton(),
+        ..options.clone()
+    };
+    let mut items = Vec::new();
+    let mut count = |item: ListedItem, section: Section, level: usize| {
+        let full = tokenizer.count(&item_output(item, options));
+        let skeleton = match section {
+            Section::Query => full,
+            _ => tokenizer.count(&item_output(item, &skeleton_options)),
+        };
+        let id = match item {
+            ListedItem::Query { node, .. } => node.id(),
+            ListedItem::Dependency(dependency) => dependency.node().id(),
+            ListedItem::Dependent(dependent) => dependent.node().id(),
+        };
+        items.push(ItemTokens {
+            id: id.to_string(),
+            section,
+            level,
+            full,
+            skeleton,
+            fate: Fate::Full,
+        });
+    };
+
+    if !options.silence_query {
+        for root in root_nodes {
+            let file = root.file_path().relative_path();
+            for node in root.matching_nodes(filter, use_full_path) {
+                let item = ListedItem::Query { node, file: &file };
+                count(item, Section::Query, 0);
+            }
+        }
+    }
+
+    if options.show_dependencies {
+        for (_, dependency) in RootNode::unique_dependencies(root_nodes) {
+            let item = ListedItem::Dependency(dependency);
+            count(item, Section::Dependency, dependency.level());
+        }
+    }
+
+    if options.show_dependents {
+        for (_, dependent) in RootNode::unique_dependents(root_nodes) {
+            // the chain runs from the dependent to the queried item
+            let level = dependent.chain().len().saturating_sub(1);
+            count(ListedItem::Dependent(dependent), Section::Dependent, level);
+        }
+    }
+    items
+}
+
+// an item may be listed for several files
+fn apply_fate(root_nodes: &mut [RootNode], item: &ItemTokens) {
+    for root in root_nodes.iter_mut() {
+        match (item.section, item.fate) {
+            (_, Fate::Full) | (Section::Query, _) => {}
+            (Section::Dependency, Fate::Skeleton) => {
+                if let Some(dependency) =
+                    root.dependencies_mut().get_mut(&item.id)
+                {
+                    dependency.node_mut().set_skeleton();
+                }
+            }
+            (Section::Dependency, Fate::Dropped) => {
+                root.dependencies_mut().remove(&item.id);
+            }
+            (Section::Dependent, Fate::Skeleton) => {
+                if let Some(dependent) = root.dependents_mut().get_mut(&item.id)
+                {
+                    dependent.node_mut().set_skeleton();
+                }
+            }
+            (Section::Dependent, Fate::Dropped) => {
+                root.dependents_mut().remove(&item.id);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(id: &str, section: Section, level: usize) -> ItemTokens {
+        ItemTokens {
+            id: id.to_string(),
+            section,
+            level,
+            full: 100,
+            skeleton: 10,
+            fate: Fate::Full,
+        }
+    }
+
+    fn items() -> Vec<ItemTokens> {
+        vec![
+            item("query", Section::Query, 0),
+            item("dependency", Section::Dependency, 1),
+            item("transitive", Section::Dependency, 2),
+            item("dependent", Section::Dependent, 1),
+            item("indirect", Section::Dependent, 2),
+        ]
+    }
+
+    fn fates(items: &[ItemTokens]) -> Vec<(&str, Fate)> {
+        items
+            .iter()
+            .map(|item| (item.id.as_str(), item.fate))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_everything_within_budget() {
+        let mut items = items();
+        degrade(&mut items, 500);
+        assert!(items.iter().all(|item| item.fate == Fate::Full));
+    }
+
+    #[test]
+    fn reduces_indirect_dependents_first() {
+        let mut items = items();
+        degrade(&mut items, 410);
+        assert_eq!(
+            fates(&items),
+            [
+                ("query", Fate::Full),
+                ("dependency", Fate::Full),
+                ("transitive", Fate::Full),
+                ("dependent", Fate::Full),
+                ("indirect", Fate::Skeleton),
+            ]
+        );
+    }
+
+    #[test]
+    fn reduces_transitive_dependencies_after_dependents() {
+        let mut items = items();
+        degrade(&mut items, 230);
+        assert_eq!(
+            fates(&items),
+            [
+                ("query", Fate::Full),
+                ("dependency", Fate::Full),
+                ("transitive", Fate::Skeleton),
+                ("dependent", Fate::Skeleton),
+                ("indirect", Fate::Skeleton),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_in_the_same_order() {
+        let mut items = items();
+        degrade(&mut items, 220);
+        assert_eq!(
+            fates(&items),
+            [
+                ("query", Fate::Full),
+                ("dependency", Fate::Full),
+                ("transitive", Fate::Skeleton),
+                ("dependent", Fate::Skeleton),
+                ("indirect", Fate::Dropped),
+            ]
+        );
+    }
+
+    #[test]
+    fn never_drops_queried_items_or_direct_dependencies() {
+        let mut items = items();
+        degrade(&mut items, 0);
+        assert_eq!(
+            fates(&items),
+            [
+                ("query", Fate::Full),
+                ("dependency", Fate::Full),
+                ("transitive", Fate::Dropped),
+                ("dependent", Fate::Dropped),
+                ("indirect", Fate::Dropped),
+            ]
+        );
+        let report = TokenReport {
+            items,
+            overhead: 10,
+            budget: Some(0),
+        };
+        assert_eq!(report.total(), 210);
+        assert!(report.exceeds_budget());
+    }
+}
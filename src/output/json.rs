@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+
+use crate::map::ListOptions;
+use crate::query::Query;
+use crate::tree::{RootNode, TreeNode};
+use crate::types::{Identifiable, RustType, Span, Visibility};
+
+use super::{render, ListedItem};
+
+/// The result of `ls` as a JSON document:
+///
+/// - `items`: the items matching the query
+/// - `dependencies`: items they depend on, with the ids that require them
+/// - `dependents`: items using them, with the chain of ids leading there
+/// - `edges`: `{from, to}` pairs, `from` refers to `to`
+///
+/// The `source` of each item is written in the code style of `options`.
+pub fn json_document(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    options: &ListOptions,
+) -> Value {
+    let mut items = Vec::new();
+    if !options.silence_query {
+        for root in root_nodes {
+            let file = root.file_path().relative_path();
+            for node in root.matching_nodes(filter, use_full_path) {
+                let item = ListedItem::Query { node, file: &file };
+                items.push(item_json(item, options));
+            }
+        }
+    }
+    let dependencies: Vec<Value> = RootNode::unique_dependencies(root_nodes)
+        .map(|(_, dependency)| {
+            item_json(ListedItem::Dependency(dependency), options)
+        })
+        .collect();
+    let dependents: Vec<Value> = RootNode::unique_dependents(root_nodes)
+        .map(|(_, dependent)| {
+            item_json(ListedItem::Dependent(dependent), options)
+        })
+        .collect();
+
+    // edges of every root node, as an item listed once may be required by
+    // items in several files
+    let mut edges = Vec::new();
+    let mut seen_edges = HashSet::new();
+    let mut add_edge = |from: &str, to: &str| {
+        if seen_edges.insert((from.to_string(), to.to_string())) {
+            edges.push(json!({ "from": from, "to": to }));
+        }
+    };
+    for root in root_nodes {
+        for dependency in root.dependencies().iter() {
+            for required_by in dependency.required_by() {
+                add_edge(required_by, dependency.node().id());
+            }
+        }
+        for dependent in root.dependents().iter() {
+            for pair in dependent.chain().windows(2) {
+                add_edge(&pair[0], &pair[1]);
+            }
+        }
+    }
+
+    json!({
+        "items": items,
+        "dependencies": dependencies,
+        "dependents": dependents,
+        "edges": edges,
+    })
+}
+
+/// An entry of `items`, `dependencies` or `dependents`, with its `source`
+/// in the code style of `options`.
+pub fn item_json(item: ListedItem, options: &ListOptions) -> Value {
+    let style = options.code_style;
+    match item {
+        ListedItem::Query { node, file } => {
+            let source = render(|w| node.print_code(w, style));
+            node_json(node, Some(file), source)
+        }
+        ListedItem::Dependency(dependency) => {
+            let node = dependency.node();
+            let source = render(|w| node.print_code(w, style));
+            let mut item = node_json(node, dependency.source(), source);
+            item["required_by"] = json!(dependency.required_by());
+            item
+        }
+        ListedItem::Dependent(dependent) => {
+            let source =
+                render(|w| dependent.print_code(w, options.show_owners, style));
+            let mut item =
+                node_json(dependent.node(), dependent.source(), source);
+            item["chain"] = json!(dependent.chain());
+            item
+        }
+    }
+}
+
+// `source` is the code of the item as printed in the other formats
+fn node_json(node: &TreeNode, file: Option<&str>, source: String) -> Value {
+    let rtype = node.rtype();
+    // ids are `kind:path`, e.g. `fn:codeclippy::map::list_map`
+    let (kind, path) = node.id().split_once(':').unwrap_or(("", node.id()));
+    let signature = match rtype {
+        RustType::Function(rust_function) => {
+            Some(rust_function.signature_str())
+        }
+        _ => None,
+    };
+    json!({
+        "id": node.id(),
+        "kind": kind,
+        "path": path,
+        "name": node.name(),
+        "visibility": visibility_name(rtype.visibility()),
+        "file": file,
+        "span": rtype.span().map(span_json),
+        "signature": signature,
+        "docs": rtype.attributes().doc_str(),
+        "attrs": rtype.attributes().attrs(),
+        "source": source,
+    })
+}
+
+fn visibility_name(visibility: &Visibility) -> &'static str {
+    match visibility {
+        Visibility::Public => "public",
+        Visibility::Restricted => "restricted",
+        Visibility::Inherited => "inherited",
+    }
+}
+
+fn span_json(span: Span) -> Value {
+    json!({
+        "start": { "line": span.start.line, "column": span.start.column },
+        "end": { "line": span.end.line, "column": span.end.column },
+    })
+}
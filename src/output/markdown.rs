@@ -0,0 +1,127 @@
+use crate::map::ListOptions;
+use crate::query::Query;
+use crate::tree::RootNode;
+use crate::writers::ClippyWriter;
+
+use super::{render, ListedItem};
+
+/// Markdown for chat interfaces: a heading per file with a fenced code
+/// block per item, followed by "Dependencies" and "Used by" sections.
+pub fn print_markdown(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    writer: &mut Box<dyn ClippyWriter>,
+    options: &ListOptions,
+) {
+    if !options.silence_query {
+        for root in root_nodes {
+            let nodes = root.matching_nodes(filter, use_full_path);
+            if nodes.is_empty() {
+                continue;
+            }
+            let file = root.file_path().relative_path();
+            let _ = writeln!(writer, "## {}\n", file);
+            for node in nodes {
+                print_item(
+                    writer,
+                    ListedItem::Query { node, file: &file },
+                    options,
+                );
+            }
+        }
+    }
+
+    if options.show_dependencies {
+        let dependencies: Vec<_> =
+            RootNode::unique_dependencies(root_nodes).collect();
+        if !dependencies.is_empty() {
+            let _ = writeln!(writer, "## Dependencies\n");
+        }
+        let mut last_source = None;
+        for (_, dependency) in dependencies {
+            file_heading(writer, dependency.source(), &mut last_source);
+            print_item(writer, ListedItem::Dependency(dependency), options);
+        }
+    }
+
+    if options.show_dependents {
+        let dependents: Vec<_> =
+            RootNode::unique_dependents(root_nodes).collect();
+        if !dependents.is_empty() {
+            let _ = writeln!(writer, "## Used by\n");
+        }
+        let mut last_source = None;
+        for (_, dependent) in dependents {
+            file_heading(writer, dependent.source(), &mut last_source);
+            print_item(writer, ListedItem::Dependent(dependent), options);
+        }
+    }
+}
+
+/// An item as a fenced code block, preceded by the chain leading to the
+/// queried item for indirect dependents.
+pub fn print_item(
+    writer: &mut Box<dyn ClippyWriter>,
+    item: ListedItem,
+    options: &ListOptions,
+) {
+    let style = options.code_style;
+    match item {
+        ListedItem::Query { node, .. } => {
+            let id = options.show_ids.then(|| node.id());
+            code_block(writer, id, |w| node.print_code(w, style));
+        }
+        ListedItem::Dependency(dependency) => {
+            let id = options.show_ids.then(|| dependency.node().id());
+            code_block(writer, id, |w| dependency.node().print_code(w, style));
+        }
+        ListedItem::Dependent(dependent) => {
+            // only indirect dependents need an explanation
+            if dependent.chain().len() > 2 {
+                let chain: Vec<String> = dependent
+                    .chain()
+                    .iter()
+                    .map(|id| format!("`{}`", id))
+                    .collect();
+                let _ = writeln!(writer, "Via {}\n", chain.join(" → "));
+            }
+            let id = options.show_ids.then(|| dependent.node().id());
+            code_block(writer, id, |w| {
+                dependent.print_code(w, options.show_owners, style)
+            });
+        }
+    }
+}
+
+// a heading for each run of items from the same file, the items keep the
+// order selected with `--order`
+fn file_heading<'a>(
+    writer: &mut Box<dyn ClippyWriter>,
+    source: Option<&'a str>,
+    last_source: &mut Option<&'a str>,
+) {
+    if *last_source != source {
+        let _ = writeln!(writer, "### {}\n", source.unwrap_or("(unknown)"));
+        *last_source = source;
+    }
+}
+
+// The fence is longer than any run of backticks in the code, which may
+// contain fenced examples in doc comments.
+fn code_block(
+    writer: &mut Box<dyn ClippyWriter>,
+    id: Option<&str>,
+    print: impl FnOnce(&mut Box<dyn ClippyWriter>),
+) {
+    let code = render(|writer| {
+        if let Some(id) = id {
+            let _ = writeln!(writer, "// id: {}", id);
+        }
+        print(writer);
+    });
+
+    let longest_run = code.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let _ = writeln!(writer, "{}rust\n{}\n{}\n", fence, code.trim_end(), fence);
+}
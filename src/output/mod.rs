@@ -0,0 +1,90 @@
+mod budget;
+mod json;
+mod markdown;
+mod xml;
+
+pub use budget::fit_budget;
+pub use json::json_document;
+pub use markdown::print_markdown;
+pub use xml::print_xml;
+
+use crate::map::ListOptions;
+use crate::tree::{Dependency, Dependent, TreeNode};
+use crate::writers::{BufferedWriter, ClippyWriter};
+
+/// How `ls` writes its results.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Rust code, each item preceded by an `@path:` header.
+    #[default]
+    Text,
+    Json,
+    Markdown,
+    Xml,
+}
+
+impl OutputFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(OutputFormat::Text),
+            "json" => Some(OutputFormat::Json),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "xml" => Some(OutputFormat::Xml),
+            _ => None,
+        }
+    }
+}
+
+/// An item in one of the sections of the output of `ls`.
+#[derive(Clone, Copy)]
+pub enum ListedItem<'a> {
+    /// An item matching the query, in `file`.
+    Query {
+        node: &'a TreeNode,
+        file: &'a str,
+    },
+    Dependency(&'a Dependency),
+    Dependent(&'a Dependent),
+}
+
+/// An item as written in the format of `options`, including its header or
+/// wrapper, e.g. the `@file:line:` header or the `<item>` element. The
+/// headings and sections around the items are left out.
+pub fn item_output(item: ListedItem, options: &ListOptions) -> String {
+    match options.format {
+        OutputFormat::Text => render(|writer| match item {
+            ListedItem::Query { node, file } => node.print_with_header(
+                writer,
+                Some(file),
+                options.show_ids,
+                options.code_style,
+            ),
+            ListedItem::Dependency(dependency) => {
+                dependency.print(writer, options.show_ids, options.code_style)
+            }
+            ListedItem::Dependent(dependent) => dependent.print(
+                writer,
+                options.show_ids,
+                options.show_owners,
+                options.code_style,
+            ),
+        }),
+        OutputFormat::Markdown => {
+            render(|writer| markdown::print_item(writer, item, options))
+        }
+        OutputFormat::Xml => {
+            render(|writer| xml::print_item(writer, item, options))
+        }
+        OutputFormat::Json => format!("{:#}", json::item_json(item, options)),
+    }
+}
+
+// what a print function (e.g. `RustType::print`) writes, as a string
+fn render(print: impl FnOnce(&mut Box<dyn ClippyWriter>)) -> String {
+    let mut writer: Box<dyn ClippyWriter> = Box::new(BufferedWriter::new());
+    print(&mut writer);
+    writer
+        .get_buffer()
+        .map(|buffer| String::from_utf8_lossy(buffer).into_owned())
+        .unwrap_or_default()
+}
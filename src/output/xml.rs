@@ -0,0 +1,137 @@
+use crate::map::ListOptions;
+use crate::query::Query;
+use crate::tree::{RootNode, TreeNode};
+use crate::writers::ClippyWriter;
+
+use super::{render, ListedItem};
+
+/// XML for prompt templates: every item is an `<item>` element with its
+/// path, kind, file and lines as attributes, grouped into `<query>`,
+/// `<dependencies>` and `<dependents>` sections of a `<context>` element.
+pub fn print_xml(
+    root_nodes: &[RootNode],
+    filter: Option<&Query>,
+    use_full_path: bool,
+    writer: &mut Box<dyn ClippyWriter>,
+    options: &ListOptions,
+) {
+    let _ = writeln!(writer, "<context>");
+
+    if !options.silence_query {
+        let _ = writeln!(writer, "<query>");
+        for root in root_nodes {
+            let file = root.file_path().relative_path();
+            for node in root.matching_nodes(filter, use_full_path) {
+                print_item(
+                    writer,
+                    ListedItem::Query { node, file: &file },
+                    options,
+                );
+            }
+        }
+        let _ = writeln!(writer, "</query>");
+    }
+
+    if options.show_dependencies {
+        let _ = writeln!(writer, "<dependencies>");
+        for (_, dependency) in RootNode::unique_dependencies(root_nodes) {
+            print_item(writer, ListedItem::Dependency(dependency), options);
+        }
+        let _ = writeln!(writer, "</dependencies>");
+    }
+
+    if options.show_dependents {
+        let _ = writeln!(writer, "<dependents>");
+        for (_, dependent) in RootNode::unique_dependents(root_nodes) {
+            print_item(writer, ListedItem::Dependent(dependent), options);
+        }
+        let _ = writeln!(writer, "</dependents>");
+    }
+
+    let _ = writeln!(writer, "</context>");
+}
+
+/// An item as an `<item>` element, with its path, kind, file and lines as
+/// attributes.
+pub fn print_item(
+    writer: &mut Box<dyn ClippyWriter>,
+    item: ListedItem,
+    options: &ListOptions,
+) {
+    let style = options.code_style;
+    let (attributes, code) = match item {
+        ListedItem::Query { node, file } => (
+            item_attributes(node, Some(file), options),
+            render(|w| node.print_code(w, style)),
+        ),
+        ListedItem::Dependency(dependency) => {
+            let node = dependency.node();
+            (
+                item_attributes(node, dependency.source(), options),
+                render(|w| node.print_code(w, style)),
+            )
+        }
+        ListedItem::Dependent(dependent) => {
+            let mut attributes =
+                item_attributes(dependent.node(), dependent.source(), options);
+            // only indirect dependents need an explanation
+            if dependent.chain().len() > 2 {
+                attributes.push(("via", dependent.chain().join(" -> ")));
+            }
+            let code =
+                render(|w| dependent.print_code(w, options.show_owners, style));
+            (attributes, code)
+        }
+    };
+    write_item(writer, &attributes, &code);
+}
+
+// `lines` is left out for items without a known location
+fn item_attributes(
+    node: &TreeNode,
+    file: Option<&str>,
+    options: &ListOptions,
+) -> Vec<(&'static str, String)> {
+    // ids are `kind:path`, e.g. `fn:codeclippy::map::list_map`
+    let (kind, path) = node.id().split_once(':').unwrap_or(("", node.id()));
+    let mut attributes =
+        vec![("path", path.to_string()), ("kind", kind.to_string())];
+    if let Some(file) = file {
+        attributes.push(("file", file.to_string()));
+    }
+    if let Some(span) = node.rtype().span() {
+        attributes
+            .push(("lines", format!("{}-{}", span.start.line, span.end.line)));
+    }
+    if options.show_ids {
+        attributes.push(("id", node.id().to_string()));
+    }
+    attributes
+}
+
+fn write_item(
+    writer: &mut Box<dyn ClippyWriter>,
+    attributes: &[(&str, String)],
+    code: &str,
+) {
+    let _ = write!(writer, "<item");
+    for (name, value) in attributes {
+        let _ = write!(writer, " {}=\"{}\"", name, escape(value, true));
+    }
+    let _ = writeln!(writer, ">\n{}\n</item>", escape(code.trim_end(), false));
+}
+
+// quotes only need escaping in attribute values
+fn escape(text: &str, in_attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if in_attribute => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
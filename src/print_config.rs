@@ -1,16 +1,24 @@
 use std::env;
 
+use crate::query::Query;
+use crate::types::CodeStyle;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PrintConfig {
-    filter: Option<String>,
+    filter: Option<Query>,
     path: Vec<String>,
+    module_path: Vec<String>,
     debug: bool,
     is_linked: bool,
     use_full_path: bool,
+    show_ids: bool,
+    code_style: CodeStyle,
+    // file of the printed items, for their `@file:line:` headers
+    source: Option<String>,
 }
 
 impl PrintConfig {
-    pub fn filter(&self) -> &Option<String> {
+    pub fn filter(&self) -> &Option<Query> {
         &self.filter
     }
 
@@ -18,6 +26,11 @@ impl PrintConfig {
         &self.path
     }
 
+    /// Module path of the file in the first element of `path`.
+    pub fn module_path(&self) -> &Vec<String> {
+        &self.module_path
+    }
+
     pub fn is_linked(&self) -> bool {
         self.is_linked
     }
@@ -26,17 +39,45 @@ impl PrintConfig {
         self.use_full_path
     }
 
+    pub fn show_ids(&self) -> bool {
+        self.show_ids
+    }
+
+    pub fn set_show_ids(&mut self, show_ids: bool) {
+        self.show_ids = show_ids;
+    }
+
+    pub fn code_style(&self) -> CodeStyle {
+        self.code_style
+    }
+
+    pub fn set_code_style(&mut self, code_style: CodeStyle) {
+        self.code_style = code_style;
+    }
+
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Print a location header before each item.
+    pub fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_string());
+    }
+
     pub fn add_to_path(&mut self, name: String) {
         self.path.push(name);
     }
 }
 
 pub struct PrintConfigBuilder {
-    filter: Option<String>,
+    filter: Option<Query>,
     path: Vec<String>,
+    module_path: Vec<String>,
     debug: bool,
     is_linked: bool,
     use_full_path: bool,
+    show_ids: bool,
+    code_style: CodeStyle,
 }
 
 impl PrintConfigBuilder {
@@ -49,13 +90,16 @@ impl PrintConfigBuilder {
         PrintConfigBuilder {
             filter: None,
             path: Vec::new(),
+            module_path: Vec::new(),
             debug,
             is_linked: false,
             use_full_path: false,
+            show_ids: false,
+            code_style: CodeStyle::default(),
         }
     }
 
-    pub fn filter(mut self, filter: Option<String>) -> Self {
+    pub fn filter(mut self, filter: Option<Query>) -> Self {
         self.filter = filter;
         self
     }
@@ -65,6 +109,11 @@ impl PrintConfigBuilder {
         self
     }
 
+    pub fn module_path(mut self, module_path: Vec<String>) -> Self {
+        self.module_path = module_path;
+        self
+    }
+
     pub fn is_linked(mut self, is_linked: bool) -> Self {
         self.is_linked = is_linked;
         self
@@ -75,13 +124,27 @@ impl PrintConfigBuilder {
         self
     }
 
+    pub fn show_ids(mut self, show_ids: bool) -> Self {
+        self.show_ids = show_ids;
+        self
+    }
+
+    pub fn code_style(mut self, code_style: CodeStyle) -> Self {
+        self.code_style = code_style;
+        self
+    }
+
     pub fn build(self) -> PrintConfig {
         PrintConfig {
             filter: self.filter,
             path: self.path,
+            module_path: self.module_path,
             debug: self.debug,
             is_linked: self.is_linked,
             use_full_path: self.use_full_path,
+            show_ids: self.show_ids,
+            code_style: self.code_style,
+            source: None,
         }
     }
 }
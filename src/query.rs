@@ -0,0 +1,259 @@
+use std::fmt;
+
+use regex::Regex;
+
+use crate::types::Attributes;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MatchMode {
+    Exact,
+    Regex,
+    Glob,
+}
+
+impl MatchMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "exact" => Some(MatchMode::Exact),
+            "regex" => Some(MatchMode::Regex),
+            "glob" => Some(MatchMode::Glob),
+            _ => None,
+        }
+    }
+}
+
+/// A query as passed via `--query`, matched against the `::` separated
+/// path of an item (e.g. `cli/ls.rs::ls_subcommand` or `TreeNode::print`).
+///
+/// The mode is taken from an explicit `re:` or `glob:` prefix, then from
+/// the mode passed in by the caller. Without either, a query whose only
+/// wildcards are `*` and `?` is treated as a glob (e.g. `handle_*`), one
+/// with other regex syntax as a regex, otherwise as an exact path.
+///
+/// Items can further be filtered by their attributes and doc comment, see
+/// `with_attr` and `with_doc`.
+#[derive(Debug, Clone)]
+pub struct Query {
+    source: String,
+    mode: MatchMode,
+    pattern: Option<Regex>,
+    attr_pattern: Option<Regex>,
+    doc_pattern: Option<Regex>,
+}
+
+impl Query {
+    pub fn new(
+        query: &str,
+        mode: Option<MatchMode>,
+    ) -> Result<Self, regex::Error> {
+        let (source, mode) = if let Some(rest) = query.strip_prefix("re:") {
+            (rest, MatchMode::Regex)
+        } else if let Some(rest) = query.strip_prefix("glob:") {
+            (rest, MatchMode::Glob)
+        } else {
+            (query, mode.unwrap_or_else(|| detect_mode(query)))
+        };
+
+        let pattern = match mode {
+            MatchMode::Exact => None,
+            MatchMode::Regex => Some(Regex::new(&format!("^(?:{})$", source))?),
+            MatchMode::Glob => Some(Regex::new(&glob_to_regex(source))?),
+        };
+
+        Ok(Query {
+            source: source.to_string(),
+            mode,
+            pattern,
+            attr_pattern: None,
+            doc_pattern: None,
+        })
+    }
+
+    /// Only select items with an attribute matching `pattern` somewhere,
+    /// e.g. `derive\(.*Serialize` or `deprecated`.
+    pub fn with_attr(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.attr_pattern = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    /// Only select items with a doc comment matching `pattern` somewhere.
+    pub fn with_doc(mut self, pattern: &str) -> Result<Self, regex::Error> {
+        self.doc_pattern = Some(Regex::new(pattern)?);
+        Ok(self)
+    }
+
+    pub fn has_attribute_filter(&self) -> bool {
+        self.attr_pattern.is_some() || self.doc_pattern.is_some()
+    }
+
+    /// Returns true if `attributes` pass the `with_attr` and `with_doc`
+    /// filters, if any.
+    pub fn matches_attributes(&self, attributes: &Attributes) -> bool {
+        let attr_matches = self.attr_pattern.as_ref().is_none_or(|pattern| {
+            attributes.attrs().iter().any(|attr| pattern.is_match(attr))
+        });
+        let doc_matches = self
+            .doc_pattern
+            .as_ref()
+            .is_none_or(|pattern| pattern.is_match(&attributes.doc_str()));
+        attr_matches && doc_matches
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn is_exact(&self) -> bool {
+        self.mode == MatchMode::Exact
+    }
+
+    /// Match a single name or a complete path, without prefix expansion.
+    pub fn matches(&self, path: &str) -> bool {
+        match &self.pattern {
+            Some(pattern) => pattern.is_match(path),
+            None => path == self.source,
+        }
+    }
+
+    /// Returns true if the query selects the item at `path` named `name`.
+    /// An item is also selected when one of its parents matches, so a
+    /// query for a struct includes its methods.
+    pub fn matches_path(&self, path: &str, name: &str) -> bool {
+        if self.matches(path) || self.matches(name) {
+            return true;
+        }
+        path.match_indices("::")
+            .any(|(index, _)| self.matches(&path[..index]))
+    }
+}
+
+impl PartialEq for Query {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+            && self.mode == other.mode
+            && self.attr_pattern.as_ref().map(Regex::as_str)
+                == other.attr_pattern.as_ref().map(Regex::as_str)
+            && self.doc_pattern.as_ref().map(Regex::as_str)
+                == other.doc_pattern.as_ref().map(Regex::as_str)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+fn detect_mode(query: &str) -> MatchMode {
+    const REGEX_CHARS: &[char] =
+        &['.', '+', '(', ')', '[', ']', '{', '}', '|', '^', '$'];
+    const GLOB_CHARS: &[char] = &['*', '?'];
+    if query.contains(REGEX_CHARS) {
+        MatchMode::Regex
+    } else if query.contains(GLOB_CHARS) {
+        MatchMode::Glob
+    } else {
+        MatchMode::Exact
+    }
+}
+
+// `*` and `?` stay within a single path segment, `**` crosses segments
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str(".*");
+            }
+            '*' => regex.push_str("[^:]*"),
+            '?' => regex.push_str("[^:]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            _ => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_mode_from_wildcards() {
+        assert_eq!(detect_mode("TreeNode::print"), MatchMode::Exact);
+        assert_eq!(detect_mode("handle_*"), MatchMode::Glob);
+        assert_eq!(detect_mode("mak?_holder"), MatchMode::Glob);
+        assert_eq!(detect_mode("print_.*"), MatchMode::Regex);
+        assert_eq!(detect_mode("(ls|map)"), MatchMode::Regex);
+    }
+
+    #[test]
+    fn prefix_overrides_detected_mode() {
+        let query = Query::new("re:handle_*", None).unwrap();
+        assert!(query.matches("handle_"));
+        assert!(query.matches("handle___"));
+        assert!(!query.matches("handle_ls"));
+
+        let query = Query::new("glob:ls", Some(MatchMode::Regex)).unwrap();
+        assert!(!query.is_exact());
+        assert!(query.matches("ls"));
+    }
+
+    #[test]
+    fn glob_wildcards_stay_within_a_segment() {
+        let glob = Regex::new(&glob_to_regex("tree::*")).unwrap();
+        assert!(glob.is_match("tree::TreeNode"));
+        assert!(!glob.is_match("tree::TreeNode::print"));
+
+        let glob = Regex::new(&glob_to_regex("tree::**")).unwrap();
+        assert!(glob.is_match("tree::TreeNode::print"));
+
+        let glob = Regex::new(&glob_to_regex("Tree?ode")).unwrap();
+        assert!(glob.is_match("TreeNode"));
+        assert!(!glob.is_match("Tree:ode"));
+    }
+
+    #[test]
+    fn glob_escapes_regex_syntax() {
+        let glob = Regex::new(&glob_to_regex("a.b+c")).unwrap();
+        assert!(glob.is_match("a.b+c"));
+        assert!(!glob.is_match("axbbc"));
+    }
+
+    #[test]
+    fn glob_character_classes() {
+        let glob = Regex::new(&glob_to_regex("[ab]x")).unwrap();
+        assert!(glob.is_match("ax"));
+        assert!(!glob.is_match("cx"));
+
+        let glob = Regex::new(&glob_to_regex("[!ab]x")).unwrap();
+        assert!(glob.is_match("cx"));
+        assert!(!glob.is_match("ax"));
+    }
+
+    #[test]
+    fn path_matches_through_parents() {
+        let query = Query::new("TreeNode", None).unwrap();
+        assert!(query.matches_path("TreeNode::print", "print"));
+        assert!(!query.matches_path("TreeNodes::print", "print"));
+    }
+}
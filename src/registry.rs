@@ -1,66 +1,140 @@
 use std::collections::HashMap;
 
-use crate::types::RustStruct;
+use crate::types::{Identifiable, RustImpl, RustType};
 
+/// Public items of all scanned files, keyed by their id (kind and
+/// qualified path, e.g. `struct:codeclippy::tree::TreeNode`) and by their
+/// qualified path.
+///
+/// Names are not unique across a crate (think of `Error` or `Config`), so
+/// lookups by name or path may return more than one item.
+#[derive(Default)]
 pub struct GlobalRegistry {
     items_by_id: HashMap<String, RegistryItem>,
-    name_to_id_mapping: HashMap<String, String>,
-    // TODO: add enums, traits, impls based on requirements
+    // qualified path -> ids, a path can refer to e.g. a struct and its impls
+    ids_by_path: HashMap<String, Vec<String>>,
+    // short name -> ids, impls are not included
+    ids_by_name: HashMap<String, Vec<String>>,
 }
 
-impl Default for GlobalRegistry {
-    fn default() -> Self {
-        GlobalRegistry {
-            items_by_id: HashMap::new(),
-            name_to_id_mapping: HashMap::new(),
-        }
+impl GlobalRegistry {
+    pub fn register_item(&mut self, item: RustType, source: Option<&str>) {
+        self.register(RegistryKind::Item(Box::new(item)), source);
     }
-}
 
-impl GlobalRegistry {
-    pub fn register_struct(
-        &mut self,
-        rust_struct: RustStruct,
-        source: Option<&str>,
-    ) {
-        if self.name_to_id_mapping.contains_key(rust_struct.name()) {
-            panic!(
-                "Detected more than one public item with the name '{}'. It is \
-                 not supported to have multiple public items with the same \
-                 name in the global registry.",
-                rust_struct.name()
-            );
+    pub fn register_impl(&mut self, rust_impl: RustImpl, source: Option<&str>) {
+        self.register(RegistryKind::Impl(rust_impl), source);
+    }
+
+    fn register(&mut self, item: RegistryKind, source: Option<&str>) {
+        let id = item.id().to_string();
+        if let Some(existing) = self.items_by_id.get_mut(&id) {
+            // inherent impls of a type can be split over several blocks,
+            // which share the id of the type
+            if let (RegistryKind::Impl(existing), RegistryKind::Impl(item)) =
+                (&mut existing.item, item)
+            {
+                existing.merge(item);
+                return;
+            }
+            // e.g. the same file scanned twice, or items behind #[cfg]
+            log::debug!("Item '{}' already registered", id);
+            return;
+        }
+        let path = match &item {
+            RegistryKind::Item(rust_type) => {
+                self.ids_by_name
+                    .entry(rust_type.name().to_string())
+                    .or_default()
+                    .push(id.clone());
+                id.split_once(':').map_or(id.as_str(), |(_, path)| path)
+            }
+            RegistryKind::Impl(rust_impl) => rust_impl.type_path(),
         }
+        .to_string();
         let registry_item = RegistryItem {
-            item: RegistryKind::Struct(rust_struct.clone()),
+            item,
+            path,
             source: source.map(|s| s.to_string()),
         };
-        self.name_to_id_mapping.insert(
-            rust_struct.name().to_string(),
-            rust_struct.id().to_string(),
-        );
-        self.items_by_id
-            .insert(rust_struct.id().to_string(), registry_item);
+        self.ids_by_path
+            .entry(registry_item.path().to_string())
+            .or_default()
+            .push(id.clone());
+        self.items_by_id.insert(id, registry_item);
     }
 
-    pub fn get_item_by_name(&self, name: &str) -> Option<&RegistryItem> {
-        if let Some(id) = self.name_to_id_mapping.get(name) {
-            return self.get_item_by_id(id);
-        }
-        None
+    /// Items (not impls) with the given short name, e.g. `Config`.
+    pub fn lookup_name(&self, name: &str) -> Lookup<'_> {
+        self.lookup_ids(self.ids_by_name.get(name), |_| true)
+    }
+
+    /// Items (not impls) at the given qualified path, e.g.
+    /// `codeclippy::tree::TreeNode`.
+    pub fn lookup_path(&self, path: &str) -> Lookup<'_> {
+        self.lookup_ids(self.ids_by_path.get(path), |item| {
+            !matches!(item.item(), RegistryKind::Impl(_))
+        })
     }
 
-    pub fn get_item_by_id(&self, id: &str) -> Option<&RegistryItem> {
-        self.items_by_id.get(id)
+    /// Impls of the type at the given qualified path.
+    pub fn impls_of(&self, path: &str) -> Vec<&RustImpl> {
+        self.ids_by_path
+            .get(path)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| match self.items_by_id.get(id)?.item() {
+                RegistryKind::Impl(rust_impl) => Some(rust_impl),
+                RegistryKind::Item(_) => None,
+            })
+            .collect()
+    }
+
+    fn lookup_ids(
+        &self,
+        ids: Option<&Vec<String>>,
+        include: impl Fn(&RegistryItem) -> bool,
+    ) -> Lookup<'_> {
+        let mut items: Vec<&RegistryItem> = ids
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.items_by_id.get(id))
+            .filter(|item| include(item))
+            .collect();
+        match items.len() {
+            0 => Lookup::NotFound,
+            1 => Lookup::Unique(items.remove(0)),
+            _ => Lookup::Ambiguous(items),
+        }
     }
 }
 
+/// Result of a lookup by name or path.
+pub enum Lookup<'a> {
+    NotFound,
+    Unique(&'a RegistryItem),
+    /// More than one item matched, the caller needs more context (e.g. the
+    /// module) to pick the right one.
+    Ambiguous(Vec<&'a RegistryItem>),
+}
+
 pub enum RegistryKind {
-    Struct(RustStruct),
+    Item(Box<RustType>),
+    Impl(RustImpl),
+}
+
+impl RegistryKind {
+    pub fn id(&self) -> &str {
+        match self {
+            RegistryKind::Item(item) => item.id(),
+            RegistryKind::Impl(rust_impl) => rust_impl.id(),
+        }
+    }
 }
 
 pub struct RegistryItem {
     item: RegistryKind,
+    path: String,
     source: Option<String>,
 }
 
@@ -68,7 +142,120 @@ impl RegistryItem {
     pub fn item(&self) -> &RegistryKind {
         &self.item
     }
+
+    /// Qualified path of the item, for impls that of the implementing
+    /// type, e.g. `codeclippy::tree::TreeNode`.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
     pub fn source(&self) -> Option<&str> {
-        self.source.as_ref().map(|s| s.as_str())
+        self.source.as_deref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::generate_id;
+    use crate::types::{RustFunction, RustStruct, StructKind, Visibility};
+
+    fn register_struct(
+        registry: &mut GlobalRegistry,
+        module: &str,
+        name: &str,
+        source: &str,
+    ) {
+        let mut rust_struct = RustStruct::new(
+            &generate_id("struct", name),
+            Visibility::Public,
+            name,
+            StructKind::Unit,
+        );
+        rust_struct.qualify_id(module);
+        registry.register_item(RustType::Struct(rust_struct), Some(source));
+    }
+
+    fn register_impl(
+        registry: &mut GlobalRegistry,
+        module: &str,
+        for_type: &str,
+        method: &str,
+    ) {
+        let method = RustFunction::new_with_data(
+            method,
+            Visibility::Public,
+            Vec::new(),
+            None,
+            None,
+            None,
+        );
+        let mut rust_impl =
+            RustImpl::new_with_data(for_type.to_string(), None, vec![method]);
+        rust_impl.qualify_id(module);
+        registry.register_impl(rust_impl, Some("lib.rs"));
+    }
+
+    #[test]
+    fn looks_up_qualified_paths() {
+        let mut registry = GlobalRegistry::default();
+        register_struct(&mut registry, "demo::inner", "Config", "inner.rs");
+
+        match registry.lookup_path("demo::inner::Config") {
+            Lookup::Unique(item) => {
+                assert_eq!(item.item().id(), "struct:demo::inner::Config");
+                assert_eq!(item.path(), "demo::inner::Config");
+                assert_eq!(item.source(), Some("inner.rs"));
+            }
+            _ => panic!("expected a unique item"),
+        }
+        assert!(matches!(
+            registry.lookup_path("crate::inner::Config"),
+            Lookup::NotFound
+        ));
+    }
+
+    #[test]
+    fn names_shared_by_modules_are_ambiguous() {
+        let mut registry = GlobalRegistry::default();
+        register_struct(&mut registry, "demo::a", "Config", "a.rs");
+        register_struct(&mut registry, "demo::b", "Config", "b.rs");
+
+        match registry.lookup_name("Config") {
+            Lookup::Ambiguous(items) => assert_eq!(items.len(), 2),
+            _ => panic!("expected an ambiguous lookup"),
+        }
+        assert!(matches!(
+            registry.lookup_path("demo::b::Config"),
+            Lookup::Unique(_)
+        ));
+        assert!(matches!(registry.lookup_name("Other"), Lookup::NotFound));
+    }
+
+    #[test]
+    fn impls_are_found_by_type_path_only() {
+        let mut registry = GlobalRegistry::default();
+        register_struct(&mut registry, "demo", "Config", "lib.rs");
+        register_impl(&mut registry, "demo", "Config", "new");
+
+        assert_eq!(registry.impls_of("demo::Config").len(), 1);
+        // the impl shares the path, but is not an item of its own
+        assert!(matches!(
+            registry.lookup_path("demo::Config"),
+            Lookup::Unique(_)
+        ));
+    }
+
+    #[test]
+    fn impl_blocks_of_a_type_are_merged() {
+        let mut registry = GlobalRegistry::default();
+        register_impl(&mut registry, "demo", "Config", "new");
+        register_impl(&mut registry, "demo", "Config", "name");
+
+        let impls = registry.impls_of("demo::Config");
+        assert_eq!(impls.len(), 1);
+        let code = impls[0].to_string();
+        assert!(code.contains("fn new"));
+        assert!(code.contains("fn name"));
     }
 }
@@ -0,0 +1,255 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use regex::Regex;
+use serde_json::Value;
+
+use super::Tokenizer;
+
+/// Byte pair encoding as used by most language models, with the merges
+/// read from a `tokenizer.json` (Hugging Face) or a `merges.txt` /
+/// `vocab.bpe` file (one `left right` pair per line, GPT-2 style).
+///
+/// Only the number of tokens is of interest, so the vocabulary itself is
+/// not needed: every symbol left after merging is one token.
+#[derive(Debug)]
+pub struct BpeTokenizer {
+    // rank of each merge, lower ranks are applied first
+    ranks: HashMap<(String, String), usize>,
+    // bytes are mapped to printable characters before merging (GPT-2),
+    // instead of marking spaces with `▁` (SentencePiece)
+    byte_level: bool,
+    pre_tokenizer: Regex,
+    // tokens per word, identifiers repeat a lot in code
+    cache: RefCell<HashMap<String, usize>>,
+}
+
+impl BpeTokenizer {
+    /// Fails if the file can not be read, is a malformed or unsupported
+    /// `tokenizer.json`, or has no merges at all.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let is_json = path.extension().is_some_and(|ext| ext == "json")
+            || content.trim_start().starts_with('{');
+        Self::from_content(&content, is_json)
+    }
+
+    fn from_content(content: &str, is_json: bool) -> io::Result<Self> {
+        let tokenizer = if is_json {
+            let json =
+                serde_json::from_str::<Value>(content).map_err(|err| {
+                    invalid_data(&format!("malformed tokenizer.json: {}", err))
+                })?;
+            Self::from_tokenizer_json(&json)?
+        } else {
+            Self::from_merges(content)
+        };
+        if tokenizer.ranks.is_empty() {
+            return Err(invalid_data("no BPE merges found"));
+        }
+        Ok(tokenizer)
+    }
+
+    fn from_tokenizer_json(json: &Value) -> io::Result<Self> {
+        let model = &json["model"];
+        if let Some(kind) = model["type"].as_str().filter(|kind| *kind != "BPE")
+        {
+            return Err(invalid_data(&format!(
+                "unsupported tokenizer model '{}', expected BPE",
+                kind
+            )));
+        }
+        let Some(merges) = model["merges"].as_array() else {
+            return Err(invalid_data("no BPE merges in tokenizer.json"));
+        };
+        // either `"left right"` or `["left", "right"]`
+        let pairs = merges.iter().filter_map(|merge| match merge {
+            Value::String(merge) => split_merge(merge),
+            Value::Array(pair) => Some((
+                pair.first()?.as_str()?.to_string(),
+                pair.get(1)?.as_str()?.to_string(),
+            )),
+            _ => None,
+        });
+        let byte_level =
+            json["pre_tokenizer"].to_string().contains("ByteLevel")
+                || json["decoder"].to_string().contains("ByteLevel");
+        Ok(Self::new(pairs, byte_level))
+    }
+
+    fn from_merges(content: &str) -> Self {
+        let pairs = content
+            .lines()
+            .filter(|line| !line.starts_with("#version"))
+            .filter_map(split_merge);
+        Self::new(pairs, true)
+    }
+
+    fn new(
+        pairs: impl Iterator<Item = (String, String)>,
+        byte_level: bool,
+    ) -> Self {
+        let mut ranks = HashMap::new();
+        for (rank, pair) in pairs.enumerate() {
+            ranks.entry(pair).or_insert(rank);
+        }
+        // GPT-2 splits words, numbers and punctuation, keeping a leading
+        // space with the piece after it
+        let pre_tokenizer = Regex::new(
+            r"'(?:[sdmt]|ll|ve|re)| ?\p{L}+| ?\p{N}+| ?[^\s\p{L}\p{N}]+|\s+",
+        )
+        .unwrap();
+        BpeTokenizer {
+            ranks,
+            byte_level,
+            pre_tokenizer,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn word_symbols(&self, word: &str) -> Vec<String> {
+        if self.byte_level {
+            word.bytes()
+                .map(|byte| byte_char(byte).to_string())
+                .collect()
+        } else {
+            word.chars().map(|c| c.to_string()).collect()
+        }
+    }
+
+    fn count_word(&self, word: &str) -> usize {
+        if let Some(count) = self.cache.borrow().get(word) {
+            return *count;
+        }
+        let mut symbols = self.word_symbols(word);
+        // merge the pair of lowest rank until none is left
+        loop {
+            let best = symbols
+                .windows(2)
+                .enumerate()
+                .filter_map(|(index, pair)| {
+                    let rank =
+                        self.ranks.get(&(pair[0].clone(), pair[1].clone()))?;
+                    Some((*rank, index))
+                })
+                .min();
+            let Some((_, index)) = best else {
+                break;
+            };
+            let right = symbols.remove(index + 1);
+            symbols[index].push_str(&right);
+        }
+        self.cache
+            .borrow_mut()
+            .insert(word.to_string(), symbols.len());
+        symbols.len()
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn count(&self, text: &str) -> usize {
+        if self.byte_level {
+            return self
+                .pre_tokenizer
+                .find_iter(text)
+                .map(|word| self.count_word(word.as_str()))
+                .sum();
+        }
+        // SentencePiece marks the start of each word with `▁`
+        text.split(' ')
+            .filter(|word| !word.is_empty())
+            .map(|word| self.count_word(&format!("▁{}", word)))
+            .sum()
+    }
+}
+
+fn split_merge(merge: &str) -> Option<(String, String)> {
+    let (left, right) = merge.split_once(' ')?;
+    Some((left.to_string(), right.to_string()))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// GPT-2 maps each byte to a printable character: printable ASCII and
+// Latin-1 map to themselves, the others to the characters from U+0100 on
+fn byte_char(byte: u8) -> char {
+    let printable = |b: u8| {
+        (b'!'..=b'~').contains(&b)
+            || (0xA1..=0xAC).contains(&b)
+            || (0xAE..=0xFF).contains(&b)
+    };
+    if printable(byte) {
+        return byte as char;
+    }
+    let offset = (0..byte).filter(|b| !printable(*b)).count() as u32;
+    char::from_u32(0x100 + offset).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merges_pairs_by_rank() {
+        let tokenizer = BpeTokenizer::from_merges(
+            "#version: 0.2\nh e\nl l\nhe ll\nhell o\n",
+        );
+        assert_eq!(tokenizer.count("hello"), 1);
+        // ` hello` starts with the byte of the space, which is not merged
+        assert_eq!(tokenizer.count("hello hello"), 3);
+        assert_eq!(tokenizer.count("hole"), 4);
+    }
+
+    #[test]
+    fn lower_ranks_are_merged_first() {
+        // `e l` takes the `e` before `h e` can, which leaves `h el`
+        let tokenizer = BpeTokenizer::from_merges("e l\nh e\nh el\n");
+        assert_eq!(tokenizer.count("hel"), 1);
+        // `h e` first leaves `he l`, which is not a merge
+        let tokenizer = BpeTokenizer::from_merges("h e\ne l\nh el\n");
+        assert_eq!(tokenizer.count("hel"), 2);
+    }
+
+    #[test]
+    fn reads_sentencepiece_merges_from_tokenizer_json() {
+        let json = json!({
+            "model": { "type": "BPE", "merges": [["▁", "a"], "▁a b"] }
+        });
+        let tokenizer = BpeTokenizer::from_tokenizer_json(&json).unwrap();
+        assert!(!tokenizer.byte_level);
+        assert_eq!(tokenizer.count("ab ab"), 2);
+        assert_eq!(tokenizer.count("ba"), 3);
+    }
+
+    #[test]
+    fn rejects_other_models() {
+        let json = json!({ "model": { "type": "Unigram" } });
+        assert!(BpeTokenizer::from_tokenizer_json(&json).is_err());
+        let json = json!({ "model": { "type": "BPE" } });
+        assert!(BpeTokenizer::from_tokenizer_json(&json).is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_or_empty_files() {
+        assert!(BpeTokenizer::from_content("{\"model\": ", true).is_err());
+        let empty_merges = r#"{"model": {"type": "BPE", "merges": []}}"#;
+        assert!(BpeTokenizer::from_content(empty_merges, true).is_err());
+        assert!(BpeTokenizer::from_content("#version: 0.2\n", false).is_err());
+        assert!(BpeTokenizer::from_content("h e\n", false).is_ok());
+    }
+
+    #[test]
+    fn maps_bytes_to_printable_characters() {
+        assert_eq!(byte_char(b'a'), 'a');
+        assert_eq!(byte_char(b' '), 'Ġ');
+        assert_eq!(byte_char(b'\n'), 'Ċ');
+        assert_eq!(byte_char(0xAD), 'Ń');
+    }
+}
@@ -0,0 +1,43 @@
+mod bpe;
+
+pub use bpe::BpeTokenizer;
+
+use std::fmt;
+
+use regex::Regex;
+
+/// Counts the tokens a language model would see for a piece of text.
+pub trait Tokenizer: fmt::Debug {
+    fn count(&self, text: &str) -> usize;
+}
+
+/// A tokenizer-independent estimate: identifiers and numbers count one
+/// token per four characters, every other non-whitespace character counts
+/// one. Typically within 20% of the BPE tokenizers used for code.
+#[derive(Debug)]
+pub struct ApproximateTokenizer {
+    pieces: Regex,
+}
+
+impl ApproximateTokenizer {
+    pub fn new() -> Self {
+        ApproximateTokenizer {
+            pieces: Regex::new(r"\w+|[^\w\s]").unwrap(),
+        }
+    }
+}
+
+impl Default for ApproximateTokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tokenizer for ApproximateTokenizer {
+    fn count(&self, text: &str) -> usize {
+        self.pieces
+            .find_iter(text)
+            .map(|piece| piece.as_str().chars().count().div_ceil(4))
+            .sum()
+    }
+}
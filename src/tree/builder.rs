@@ -1,16 +1,20 @@
 use super::dependencies::find_dependencies;
-use super::dependents::find_dependents;
+use std::collections::HashSet;
+
+use super::dependents::{find_dependents, DependentTarget};
 use super::initialize::ChunkInitializer;
 use crate::file_visitor::RustFileVisitor;
 use crate::print_config::PrintConfigBuilder;
-use crate::registry::GlobalRegistry;
-use crate::tree::{RootNode, TreeNode};
-use crate::types::Identifiable;
+use crate::query::Query;
+use crate::registry::{GlobalRegistry, Lookup};
+use crate::tree::{Depth, NameResolver, RootNode, TreeNode};
+use crate::types::{Identifiable, RustType};
 
 pub struct TreeBuilder {
     visitors: Vec<RustFileVisitor>,
     global_registry: GlobalRegistry,
     use_full_path: bool,
+    depth: Depth,
 }
 
 impl TreeBuilder {
@@ -19,12 +23,19 @@ impl TreeBuilder {
             visitors,
             global_registry: GlobalRegistry::default(),
             use_full_path,
+            depth: Depth::default(),
         }
     }
 
+    /// How many hops of dependencies to link, direct ones only by default.
+    pub fn with_depth(mut self, depth: Depth) -> Self {
+        self.depth = depth;
+        self
+    }
+
     pub fn initialize_root_nodes(
         &mut self,
-        filter: Option<&str>,
+        filter: Option<&Query>,
         link_dependencies: bool,
         link_dependents: bool,
     ) -> Vec<RootNode> {
@@ -38,15 +49,22 @@ impl TreeBuilder {
             .collect();
 
         if !self.use_full_path {
-            self.validate_chunks_for_conflicts(&root_nodes, filter);
+            self.report_ambiguous_query(filter);
         }
 
+        if !link_dependencies && !link_dependents {
+            return root_nodes;
+        }
+        // linking adds dependencies and dependents, but no items or `use`
+        // declarations, so one resolver serves the whole query
+        let resolver = NameResolver::new(&root_nodes);
+
         if link_dependencies {
-            self.link_dependencies(&mut root_nodes, filter);
+            self.link_dependencies(&mut root_nodes, filter, &resolver);
         }
 
         if link_dependents {
-            self.link_dependents(&mut root_nodes, filter);
+            self.link_dependents(&mut root_nodes, filter, &resolver);
         }
 
         root_nodes
@@ -54,115 +72,282 @@ impl TreeBuilder {
 
     fn link_dependencies(
         &mut self,
-        root_nodes: &mut Vec<RootNode>,
-        filter: Option<&str>,
+        root_nodes: &mut [RootNode],
+        filter: Option<&Query>,
+        resolver: &NameResolver,
     ) {
         for root in root_nodes {
-            if let Some(filter_str) = filter {
+            if let Some(query) = filter {
                 let config = PrintConfigBuilder::new()
-                    .filter(Some(filter_str.to_string()))
-                    .path(vec![root.file_path().relative_path().to_string()])
+                    .filter(Some(query.clone()))
+                    .path(root.path())
+                    .module_path(root.file_module_path())
                     .is_linked(false)
                     .use_full_path(self.use_full_path)
                     .build();
 
-                find_dependencies(root, &self.global_registry, &config);
+                find_dependencies(
+                    root,
+                    resolver,
+                    &self.global_registry,
+                    &config,
+                    self.depth,
+                );
             }
         }
     }
 
     fn link_dependents(
         &mut self,
-        root_nodes: &mut Vec<RootNode>,
-        filter: Option<&str>,
+        root_nodes: &mut [RootNode],
+        filter: Option<&Query>,
+        resolver: &NameResolver,
     ) {
+        let query = filter.expect("Filter must be set to link dependents");
+        // the attribute filters are only applied when matching by pattern
+        if !query.is_exact() || query.has_attribute_filter() {
+            self.link_dependents_by_pattern(root_nodes, query, resolver);
+            return;
+        }
+
+        // an id selects exactly one item
+        if let Some((root_index, target)) =
+            find_target_by_id(root_nodes, query.as_str())
+        {
+            link_dependents_of_target(
+                root_nodes, root_index, target, resolver, self.depth,
+            );
+            return;
+        }
+
         let filter_path = if !self.use_full_path {
-            filter
-                .expect("Filter must be set when using full path")
-                .split("::")
-                .collect::<Vec<&str>>()
+            query.as_str().split("::").collect::<Vec<&str>>()
         } else {
             // remove first element (filename) in case search is
             // scoped to single file
-            filter
-                .expect("Filter must be set when using full path")
-                .split("::")
-                .skip(1)
-                .collect::<Vec<&str>>()
+            query.as_str().split("::").skip(1).collect::<Vec<&str>>()
         };
 
         // Find the root and target node that matches the filter path
-        match find_root_node(&root_nodes, &filter_path[0]) {
-            Some(root_index) => {
-                // Check if main element is public
-                let target_item = root_nodes[root_index]
-                    .find_child_by_name(&filter_path[0])
+        match find_root_node(root_nodes, &filter_path) {
+            Some((root_index, depth)) => {
+                let filter_path = filter_path[depth..].to_vec();
+                let root = &root_nodes[root_index];
+                let target_item = root
+                    .find_child_by_name(filter_path[0])
                     .expect("Rust item not found")
                     .rtype()
                     .clone();
-
-                // TODO: if searching for sub-element (e.g. method of a
-                // struct, first check if sub-element is public. If sub
-                // is not public, we only need to check rust_item (self))
-
-                if target_item.is_public() {
-                    // If rust item is public, assume it can be called
-                    // in any root node
-                    for root_node in root_nodes {
-                        find_dependents(
-                            root_node,
-                            &target_item,
-                            filter_path.clone(),
-                        );
-                    }
-                } else {
-                    // If the rust item is not public, traverse only
-                    // through the node in which it was found
-                    let root_node = &mut root_nodes[root_index];
-                    find_dependents(root_node, &target_item, filter_path);
-                }
+                let item_path = format!(
+                    "{}::{}",
+                    root.module_path().join("::"),
+                    target_item.name()
+                );
+                // a method (e.g. `Foo::new`) is a target by itself
+                let method = filter_path.get(1).and_then(|name| {
+                    target_item
+                        .methods()?
+                        .iter()
+                        .find(|method| method.name() == *name)
+                });
+                let target = match method {
+                    Some(method) => DependentTarget::method(
+                        RustType::Function(Box::new(method.clone())),
+                        item_path,
+                    ),
+                    None => DependentTarget::new(target_item, item_path),
+                };
+                link_dependents_of_target(
+                    root_nodes, root_index, target, resolver, self.depth,
+                );
             }
             None => {
                 log::info!(
                     "Item '{}' not found at root level of any file",
-                    filter_path[0]
+                    query
                 );
             }
         }
     }
 
-    fn validate_chunks_for_conflicts(
-        &self,
-        chunks: &[RootNode],
-        filter: Option<&str>,
+    fn link_dependents_by_pattern(
+        &mut self,
+        root_nodes: &mut [RootNode],
+        query: &Query,
+        resolver: &NameResolver,
     ) {
-        if let Some(filter_str) = filter {
-            let first_component = filter_str.split("::").next().unwrap();
-            let potential_conflicts: Vec<_> = chunks
+        // every root level item matched by the pattern is a target
+        let use_full_path = self.use_full_path;
+        let targets: Vec<(usize, DependentTarget)> = root_nodes
+            .iter()
+            .enumerate()
+            .flat_map(|(root_index, root)| {
+                root.children()
+                    .iter()
+                    .filter(move |child| {
+                        let config = root.child_print_config(
+                            child,
+                            Some(query),
+                            use_full_path,
+                        );
+                        child.should_print(&config)
+                    })
+                    .map(move |child| {
+                        (root_index, root_child_target(root, child))
+                    })
+            })
+            .collect();
+
+        if targets.is_empty() {
+            log::info!(
+                "Pattern '{}' matches no item at root level of any file",
+                query
+            );
+        }
+
+        for (root_index, target) in targets {
+            link_dependents_of_target(
+                root_nodes, root_index, target, resolver, self.depth,
+            );
+        }
+    }
+
+    // An exact query matching items in several modules lists all of them,
+    // the ids tell them apart. A pattern is expected to match many items.
+    fn report_ambiguous_query(&self, filter: Option<&Query>) {
+        let Some(query) = filter.filter(|query| query.is_exact()) else {
+            return;
+        };
+        let name = query.as_str().split("::").next().unwrap_or_default();
+        if let Lookup::Ambiguous(items) = self.global_registry.lookup_name(name)
+        {
+            let matches: Vec<String> = items
                 .iter()
-                .filter(|tree| tree.has_child_named(first_component))
+                .map(|item| {
+                    format!(
+                        "  {} ({})",
+                        item.item().id(),
+                        item.source().unwrap_or("unknown file")
+                    )
+                })
                 .collect();
+            eprintln!(
+                "'{}' is ambiguous, it matches:\n{}\nPass one of the ids to \
+                 --query to select a single item.",
+                name,
+                matches.join("\n")
+            );
+        }
+    }
+}
 
-            if potential_conflicts.len() > 1 {
-                panic!(
-                    "Potential conflict found. More than one chunk has a node \
-                     named {}. Please specify a more specific filter.",
-                    first_component
-                );
+// Walks the references to `target` up to `depth` levels, e.g. the callers
+// of its callers at depth 2. Every item is visited once, so cycles (such as
+// recursive functions) end the walk.
+fn link_dependents_of_target(
+    root_nodes: &mut [RootNode],
+    root_index: usize,
+    target: DependentTarget,
+    resolver: &NameResolver,
+    depth: Depth,
+) {
+    // references are resolved against the qualified path of the target,
+    // so an item with the same name in another module is not a match
+    let mut visited = HashSet::from([target.item().id().to_string()]);
+    let mut frontier = vec![(root_index, target)];
+    let mut level = 1;
+
+    while depth.includes(level) && !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for (root_index, target) in &frontier {
+            // If rust item is public, assume it can be called in any root
+            // node, otherwise only in the node in which it was found
+            let root_indices = if target.item().is_public() {
+                (0..root_nodes.len()).collect()
+            } else {
+                vec![*root_index]
+            };
+            for index in root_indices {
+                for found in
+                    find_dependents(&mut root_nodes[index], target, resolver)
+                {
+                    if visited.insert(found.item().id().to_string()) {
+                        next_frontier.push((index, found));
+                    }
+                }
             }
         }
+        frontier = next_frontier;
+        level += 1;
     }
 }
 
-fn find_root_node(root_nodes: &[RootNode], target_name: &str) -> Option<usize> {
+fn root_child_target(root: &RootNode, child: &TreeNode) -> DependentTarget {
+    DependentTarget::new(
+        child.rtype().clone(),
+        format!("{}::{}", root.module_path().join("::"), child.name()),
+    )
+}
+
+fn find_root_node(
+    root_nodes: &[RootNode],
+    filter_path: &[&str],
+) -> Option<(usize, usize)> {
     for (i, root_node) in root_nodes.iter().enumerate() {
-        if root_node.find_child_by_name(target_name).is_some() {
-            return Some(i);
+        // a path is either relative to the file (and its inline modules),
+        // or starts at the crate root, e.g. crate::tree::TreeBuilder
+        let crate_module_path = root_node.crate_module_path();
+        for prefix in [
+            root_node.inline_path(),
+            root_node.module_path(),
+            &crate_module_path,
+        ] {
+            let depth = prefix.len();
+            if filter_path.len() > depth
+                && filter_path.iter().zip(prefix).all(|(a, b)| a == b)
+                && root_node.has_child_named(filter_path[depth])
+            {
+                return Some((i, depth));
+            }
         }
     }
     None
 }
 
+// an id selects a root level item, or a method of one
+fn find_target_by_id(
+    root_nodes: &[RootNode],
+    id: &str,
+) -> Option<(usize, DependentTarget)> {
+    root_nodes
+        .iter()
+        .enumerate()
+        .find_map(|(root_index, root)| {
+            root.children().iter().find_map(|child| {
+                if child.id() == id {
+                    return Some((root_index, root_child_target(root, child)));
+                }
+                let method = child
+                    .rtype()
+                    .methods()?
+                    .iter()
+                    .find(|method| method.id() == id)?;
+                let type_path = format!(
+                    "{}::{}",
+                    root.module_path().join("::"),
+                    child.name()
+                );
+                Some((
+                    root_index,
+                    DependentTarget::method(
+                        RustType::Function(Box::new(method.clone())),
+                        type_path,
+                    ),
+                ))
+            })
+        })
+}
+
 fn find_node_by_path<'a>(
     node: &'a TreeNode,
     path: &[&str],
@@ -1,99 +1,207 @@
-use std::collections::HashMap;
-
-use log;
+use std::collections::{HashMap, HashSet};
 
+use super::dependents::{
+    enum_item_names, fn_signature_item_names, macro_item_names,
+    struct_item_names, trait_item_names, type_item_names, value_item_names,
+};
 use crate::print_config::PrintConfig;
-use crate::registry::{GlobalRegistry, RegistryKind};
-use crate::tree::{RootNode, TreeNode};
-use crate::types::{RustStruct, RustType};
+use crate::registry::{GlobalRegistry, Lookup, RegistryItem, RegistryKind};
+use crate::tree::{
+    absolute_path, Depth, NameResolver, Order, Resolution, RootNode, TreeNode,
+};
+use crate::types::{CodeStyle, Identifiable, RustFunction, RustType};
 use crate::writers::ClippyWriter;
 
+#[derive(Default)]
 pub struct Dependencies {
-    items_by_id: HashMap<String, Dependency>,
+    // in the order they are printed, see `sort`
+    items: Vec<Dependency>,
+    index_by_id: HashMap<String, usize>,
 }
 
-impl Default for Dependencies {
-    fn default() -> Self {
-        Dependencies {
-            items_by_id: HashMap::new(),
+impl Dependencies {
+    /// An item required by more than one item is registered once, with
+    /// all of them in `required_by`.
+    pub fn register_item(&mut self, dependency: Dependency) {
+        log::debug!("adding dependency: {}", dependency.node().name());
+        let node_id = dependency.node().id().to_string();
+        match self.index_by_id.get(&node_id) {
+            Some(&index) => {
+                let existing = &mut self.items[index];
+                for id in dependency.required_by {
+                    if !existing.required_by.contains(&id) {
+                        existing.required_by.push(id);
+                    }
+                }
+            }
+            None => {
+                self.index_by_id.insert(node_id, self.items.len());
+                self.items.push(dependency);
+            }
         }
     }
-}
 
-impl Dependencies {
-    pub fn register_item(&mut self, node: TreeNode, source: Option<&str>) {
-        log::debug!("adding dependency: {}", node.clone().name());
-        let node_id = node.id().to_string();
-        let dependency = Dependency::new(node, source);
-        self.items_by_id.insert(node_id, dependency);
+    pub fn contains(&self, id: &str) -> bool {
+        self.index_by_id.contains_key(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dependency> {
+        self.items.iter()
+    }
+
+    pub fn get(&self, id: &str) -> Option<&Dependency> {
+        self.index_by_id.get(id).map(|&index| &self.items[index])
+    }
+
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Dependency> {
+        self.index_by_id
+            .get(id)
+            .map(|&index| &mut self.items[index])
     }
 
-    pub fn len(&self) -> usize {
-        self.items_by_id.len()
+    pub fn remove(&mut self, id: &str) -> Option<Dependency> {
+        let index = self.index_by_id.remove(id)?;
+        let dependency = self.items.remove(index);
+        self.reindex();
+        Some(dependency)
     }
 
-    pub fn print(&self, writer: &mut Box<dyn ClippyWriter>) {
-        for (_, dependency) in &self.items_by_id {
-            self.print_dependency(writer, dependency);
+    /// Sort the dependencies, and the items requiring each of them, in
+    /// `order`. Registration order depends on the order names are
+    /// resolved in, so output is only stable once sorted.
+    pub fn sort(&mut self, order: Order) {
+        self.items.sort_by(|a, b| {
+            order.compare((a.node(), a.source()), (b.node(), b.source()))
+        });
+        if order == Order::Topological {
+            self.items = topological_order(std::mem::take(&mut self.items));
         }
+        for dependency in &mut self.items {
+            dependency.required_by.sort();
+        }
+        self.reindex();
     }
 
-    fn print_dependency(
-        &self,
-        writer: &mut Box<dyn ClippyWriter>,
-        dependency: &Dependency,
-    ) {
-        let _ = writeln!(writer, "@{}:", dependency.source().unwrap_or(""),);
+    fn reindex(&mut self) {
+        self.index_by_id = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, dependency)| {
+                (dependency.node().id().to_string(), index)
+            })
+            .collect();
+    }
+}
 
-        match dependency.node().rtype() {
-            RustType::Function(rust_function) => {
-                let _ = write!(writer, "{}", rust_function);
-            }
-            RustType::Struct(rust_struct) => {
-                let _ = write!(writer, "{}", rust_struct);
-            }
-            RustType::Enum(rust_enum) => {
-                let _ = write!(writer, "{}", rust_enum);
-            }
-            RustType::Trait(rust_trait) => {
-                let _ = write!(writer, "{}", rust_trait);
+// Kahn's algorithm: repeatedly take the first item, in the given order,
+// whose own dependencies are all placed. On a cycle the first remaining
+// item is taken.
+fn topological_order(mut remaining: Vec<Dependency>) -> Vec<Dependency> {
+    // number of dependencies not yet placed, per item requiring them
+    let mut pending: HashMap<String, usize> = HashMap::new();
+    for dependency in &remaining {
+        for id in &dependency.required_by {
+            *pending.entry(id.clone()).or_default() += 1;
+        }
+    }
+    let mut sorted = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let index = remaining
+            .iter()
+            .position(|dependency| {
+                pending.get(dependency.node().id()).is_none_or(|n| *n == 0)
+            })
+            .unwrap_or(0);
+        let dependency = remaining.remove(index);
+        for id in &dependency.required_by {
+            if let Some(count) = pending.get_mut(id) {
+                *count = count.saturating_sub(1);
             }
         }
+        sorted.push(dependency);
     }
+    sorted
 }
 
-struct Dependency {
+pub struct Dependency {
     node: TreeNode,
     source: Option<String>,
+    // module the item is defined in, its own dependencies are resolved
+    // from there
+    module_path: Vec<String>,
+    // ids of the items referring to this one
+    required_by: Vec<String>,
+    // 1 for a dependency of a queried item, 2 for a dependency of that
+    // one and so on
+    level: usize,
 }
 
 impl Dependency {
-    pub fn new(node: TreeNode, source: Option<&str>) -> Self {
+    pub fn new(
+        node: TreeNode,
+        source: Option<&str>,
+        module_path: Vec<String>,
+        required_by: &str,
+    ) -> Self {
         Dependency {
             node,
             source: source.map(|s| s.to_string()),
+            module_path,
+            required_by: vec![required_by.to_string()],
+            level: 1,
         }
     }
 
     pub fn node(&self) -> &TreeNode {
         &self.node
     }
+
+    pub fn node_mut(&mut self) -> &mut TreeNode {
+        &mut self.node
+    }
+
     pub fn source(&self) -> Option<&str> {
-        self.source.as_ref().map(|s| s.as_str())
+        self.source.as_deref()
+    }
+
+    pub fn module_path(&self) -> &[String] {
+        &self.module_path
+    }
+
+    pub fn required_by(&self) -> &[String] {
+        &self.required_by
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// The item preceded by its `@file:line:` header, and its id if
+    /// `show_ids` is set.
+    pub fn print(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        show_ids: bool,
+        code_style: CodeStyle,
+    ) {
+        let source = self.source().unwrap_or("");
+        self.node
+            .print_with_header(writer, Some(source), show_ids, code_style);
     }
 }
 
 pub fn find_dependencies(
     root: &mut RootNode,
+    resolver: &NameResolver,
     registry: &GlobalRegistry,
     config: &PrintConfig,
+    depth: Depth,
 ) {
     let mut dependencies = Dependencies::default();
-    let local_items_map: HashMap<String, TreeNode> = root
-        .children()
-        .iter()
-        .map(|item| (item.name().to_string(), item.clone()))
-        .collect();
+    let module_path = root.module_path().clone();
+    // the queried items themselves are never listed as dependencies
+    let mut printed_ids = HashSet::new();
 
     for child in root.children_mut().iter_mut() {
         let mut inner_config = config.clone();
@@ -101,20 +209,82 @@ pub fn find_dependencies(
 
         find_dependencies_recursive(
             child,
+            resolver,
             registry,
             &mut dependencies,
-            &local_items_map,
+            &mut printed_ids,
+            &module_path,
             &inner_config,
         );
     }
+
+    let direct_ids: Vec<String> = dependencies
+        .iter()
+        .map(|dependency| dependency.node().id().to_string())
+        .collect();
+    follow_dependencies(
+        &mut dependencies,
+        direct_ids,
+        &printed_ids,
+        resolver,
+        registry,
+        depth,
+    );
     root.set_dependencies(dependencies);
 }
 
+// Breadth first walk from the direct dependencies. An item registered once
+// is not visited again, which also ends the walk on cycles.
+fn follow_dependencies(
+    dependencies: &mut Dependencies,
+    mut frontier: Vec<String>,
+    printed_ids: &HashSet<String>,
+    resolver: &NameResolver,
+    registry: &GlobalRegistry,
+    depth: Depth,
+) {
+    let mut level = 2;
+    while depth.includes(level) && !frontier.is_empty() {
+        let mut next_frontier = Vec::new();
+        for id in &frontier {
+            let Some(dependency) = dependencies.get(id) else {
+                continue;
+            };
+            let mut node = dependency.node().clone();
+            process_function_node(&mut node);
+            let found = resolve_dependencies(
+                &node,
+                dependency.module_path(),
+                resolver,
+                registry,
+                &|_| false,
+            );
+            for mut found in found {
+                found.level = level;
+                let found_id = found.node().id().to_string();
+                if printed_ids.contains(&found_id) {
+                    continue;
+                }
+                // known items only get another `required_by`
+                let is_new = !dependencies.contains(&found_id);
+                dependencies.register_item(found);
+                if is_new {
+                    next_frontier.push(found_id);
+                }
+            }
+        }
+        frontier = next_frontier;
+        level += 1;
+    }
+}
+
 fn find_dependencies_recursive(
     tree: &mut TreeNode,
+    resolver: &NameResolver,
     global_registry: &GlobalRegistry,
     dependencies: &mut Dependencies,
-    local_items_map: &HashMap<String, TreeNode>,
+    printed_ids: &mut HashSet<String>,
+    module_path: &[String],
     config: &PrintConfig,
 ) {
     log::debug!("Processing node: {}", tree.name());
@@ -123,13 +293,19 @@ fn find_dependencies_recursive(
     // get first item from vector
     // if node is printed, collects its dependencies
     if tree.should_print(config) {
-        collect_dependencies(
+        printed_ids.insert(tree.id().to_string());
+        // if name is in config.path, skip as cant depend on self
+        let is_self =
+            |name: &str| config.path().iter().any(|item| item == name);
+        for dependency in resolve_dependencies(
             tree,
+            module_path,
+            resolver,
             global_registry,
-            local_items_map,
-            dependencies,
-            config,
-        );
+            &is_self,
+        ) {
+            dependencies.register_item(dependency);
+        }
     }
 
     // Process child nodes directly here
@@ -141,9 +317,11 @@ fn find_dependencies_recursive(
             // Recursively call this function on each child
             find_dependencies_recursive(
                 child,
+                resolver,
                 global_registry,
                 dependencies,
-                local_items_map,
+                printed_ids,
+                module_path,
                 &inner_config,
             );
         } else {
@@ -158,62 +336,255 @@ fn process_function_node(tree: &mut TreeNode) {
     }
 }
 
-fn collect_dependencies(
+// dependencies of `tree`, with paths resolved as written in `module_path`
+fn resolve_dependencies(
     tree: &TreeNode,
+    module_path: &[String],
+    resolver: &NameResolver,
     global_registry: &GlobalRegistry,
-    local_items_map: &HashMap<String, TreeNode>,
-    dependencies: &mut Dependencies,
-    config: &PrintConfig,
-) {
+    is_self: &dyn Fn(&str) -> bool,
+) -> Vec<Dependency> {
     log::debug!("Collecting dependencies for node: {}", tree.name());
-    match &tree.rtype() {
+    let item_names: Vec<String> = match &tree.rtype() {
         RustType::Function(rust_function) => {
-            // List of all instantiated structs from the function
-            let instantiated_struct_names: Vec<_> =
+            // items instantiated in the body, and types in the signature
+            let mut item_names: Vec<String> =
                 rust_function.instantiated_items().iter().cloned().collect();
+            item_names.extend(fn_signature_item_names(rust_function));
+            item_names
+        }
+        RustType::Struct(rust_struct) => struct_item_names(rust_struct),
+        RustType::Enum(rust_enum) => enum_item_names(rust_enum),
+        RustType::Trait(rust_trait) => trait_item_names(rust_trait),
+        RustType::Const(rust_const) => {
+            value_item_names(rust_const.ty(), rust_const.expr())
+        }
+        RustType::Static(rust_static) => {
+            value_item_names(rust_static.ty(), rust_static.expr())
+        }
+        RustType::TypeAlias(rust_type_alias) => {
+            type_item_names(rust_type_alias.ty())
+        }
+        RustType::Macro(rust_macro) => macro_item_names(rust_macro),
+    };
 
-            for name in &instantiated_struct_names {
-                // if name is in config.path, skip as cant depend on self
-                if config.path().contains(&name) {
+    let mut dependencies = Vec::new();
+    for path in &item_names {
+        match resolver.resolve(module_path, path) {
+            Resolution::Item(qualified_path) => {
+                let Some((node, source)) = resolver.node(&qualified_path)
+                else {
                     continue;
+                };
+                let node_module_path = parent_path(&qualified_path);
+                // `Type::method` (or `value.method()`) only depends on the
+                // method, which may be a sibling of the current one
+                if let Some(method) = find_method(node, path) {
+                    if method.id() != tree.id() {
+                        dependencies.push(Dependency::new(
+                            method,
+                            Some(source),
+                            node_module_path,
+                            tree.id(),
+                        ));
+                    }
+                } else if !is_self(node.name()) {
+                    dependencies.push(Dependency::new(
+                        node.clone(),
+                        Some(source),
+                        node_module_path,
+                        tree.id(),
+                    ));
                 }
-                // Check if the struct exists in local items
-                if let Some(local_item) = local_items_map.get(name) {
-                    let node = (*local_item).clone();
-                    let source = config.path().first().map(|s| s.as_str());
-                    dependencies.register_item(node.clone(), source);
+            }
+            Resolution::External => {}
+            // not resolvable via scanned modules, try global registry
+            Resolution::Unknown => {
+                let name = path.rsplit("::").next().unwrap_or(path);
+                if is_self(name) {
+                    continue;
                 }
-                // If not in the local items, try global registry
-                else if let Some(registry_item) =
-                    global_registry.get_item_by_name(name)
-                {
-                    let RegistryKind::Struct(rust_struct) =
-                        &registry_item.item();
-                    let node = create_struct_node_from_registry(rust_struct);
-                    dependencies
-                        .register_item(node.clone(), registry_item.source());
+                // a qualified path is more specific than the name alone
+                let crate_path = absolute_path(module_path, path);
+                let lookup = match global_registry.lookup_path(&crate_path) {
+                    Lookup::NotFound => global_registry.lookup_name(name),
+                    lookup => lookup,
+                };
+                match lookup {
+                    Lookup::Unique(registry_item) => {
+                        if let Some(node) = create_node_from_registry(
+                            global_registry,
+                            registry_item,
+                        ) {
+                            dependencies.push(Dependency::new(
+                                node,
+                                registry_item.source(),
+                                parent_path(registry_item.path()),
+                                tree.id(),
+                            ));
+                        }
+                    }
+                    Lookup::Ambiguous(registry_items) => {
+                        log::debug!(
+                            "Skipping ambiguous dependency '{}', found {} \
+                             items with that name",
+                            path,
+                            registry_items.len()
+                        );
+                    }
+                    Lookup::NotFound => {}
                 }
             }
         }
-        RustType::Struct(rust_struct) => {
-            log::warn!(
-                "Dependencies of struct: {} not yet handled",
-                rust_struct.name()
-            );
-        }
-        _ => {
-            // currently just support functions and structs
-            panic!("Unhandled RustType variant: {:?}", tree.rtype());
-        }
     }
+    dependencies
+}
+
+// e.g. `crate::tree` for `crate::tree::TreeNode`
+fn parent_path(qualified_path: &str) -> Vec<String> {
+    let mut segments: Vec<String> = qualified_path
+        .split("::")
+        .map(ToString::to_string)
+        .collect();
+    segments.pop();
+    segments
+}
+
+// method of `node` named by the last segment of `path`, e.g. `Foo::new`
+fn find_method(node: &TreeNode, path: &str) -> Option<TreeNode> {
+    let (_, method_name) = path.rsplit_once("::")?;
+    if method_name == node.name() {
+        return None;
+    }
+    node.rtype()
+        .methods()?
+        .iter()
+        .find(|method| method.name() == method_name)
+        .map(|method| {
+            TreeNode::new(RustType::Function(Box::new(method.clone())))
+        })
 }
 
-fn create_struct_node_from_registry(s: &RustStruct) -> TreeNode {
-    let mut node = TreeNode::new(RustType::Struct(s.clone()));
-    for method in s.methods().unwrap_or(&vec![]) {
-        let method_node = TreeNode::new(RustType::Function(method.clone()));
+fn create_node_from_registry(
+    global_registry: &GlobalRegistry,
+    registry_item: &RegistryItem,
+) -> Option<TreeNode> {
+    let RegistryKind::Item(rust_type) = registry_item.item() else {
+        return None;
+    };
+    let mut rust_type = rust_type.as_ref().clone();
+
+    // include methods of impls in other files than the type itself
+    let known_methods: Vec<String> = rust_type
+        .methods()
+        .into_iter()
+        .flatten()
+        .map(|method| method.id().to_string())
+        .collect();
+    let other_methods: Vec<RustFunction> = global_registry
+        .impls_of(registry_item.path())
+        .into_iter()
+        .flat_map(|rust_impl| rust_impl.methods.iter().flatten())
+        .filter(|method| !known_methods.iter().any(|id| id == method.id()))
+        .cloned()
+        .collect();
+    match &mut rust_type {
+        RustType::Struct(rust_struct) => rust_struct.add_methods(other_methods),
+        RustType::Enum(rust_enum) => rust_enum.add_methods(other_methods),
+        _ => {}
+    }
+
+    let mut node = TreeNode::new(rust_type.clone());
+    for method in rust_type.methods().unwrap_or(&vec![]) {
+        let method_node =
+            TreeNode::new(RustType::Function(Box::new(method.clone())));
         node.add_child(method_node);
     }
+    Some(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::LineColumn;
+
+    use super::*;
+    use crate::types::{Span, Visibility};
+
+    fn dependency(name: &str, line: usize, required_by: &[&str]) -> Dependency {
+        let mut function = RustFunction::new_with_data(
+            name,
+            Visibility::Public,
+            Vec::new(),
+            None,
+            None,
+            None,
+        );
+        let start = LineColumn { line, column: 0 };
+        function.set_span(Span::new(start, start));
+        let node = TreeNode::new(RustType::Function(Box::new(function)));
+        let mut dependency =
+            Dependency::new(node, Some("lib.rs"), Vec::new(), required_by[0]);
+        dependency.required_by =
+            required_by.iter().map(ToString::to_string).collect();
+        dependency
+    }
 
-    node
+    fn ids(dependencies: &[Dependency]) -> Vec<&str> {
+        dependencies.iter().map(|d| d.node().id()).collect()
+    }
+
+    #[test]
+    fn dependencies_come_before_the_items_requiring_them() {
+        // `query` calls `parse` and `render`, `parse` calls `lex`
+        let sorted = topological_order(vec![
+            dependency("parse", 1, &["fn:query"]),
+            dependency("render", 2, &["fn:query"]),
+            dependency("lex", 3, &["fn:parse"]),
+        ]);
+        // `render` is free to go first, `parse` waits for `lex`
+        assert_eq!(ids(&sorted), ["fn:render", "fn:lex", "fn:parse"]);
+    }
+
+    #[test]
+    fn ties_keep_the_given_order() {
+        let sorted = topological_order(vec![
+            dependency("b", 1, &["fn:query"]),
+            dependency("a", 2, &["fn:query"]),
+        ]);
+        assert_eq!(ids(&sorted), ["fn:b", "fn:a"]);
+    }
+
+    #[test]
+    fn cycles_take_the_first_remaining_item() {
+        let sorted = topological_order(vec![
+            dependency("even", 1, &["fn:query", "fn:odd"]),
+            dependency("odd", 2, &["fn:even"]),
+        ]);
+        assert_eq!(ids(&sorted), ["fn:even", "fn:odd"]);
+    }
+
+    #[test]
+    fn sorts_by_position_then_topologically() {
+        let mut dependencies = Dependencies::default();
+        dependencies.register_item(dependency("lex", 3, &["fn:parse"]));
+        dependencies.register_item(dependency("parse", 2, &["fn:query"]));
+        dependencies.register_item(dependency("emit", 1, &["fn:query"]));
+
+        dependencies.sort(Order::Position);
+        let sorted: Vec<&str> =
+            dependencies.iter().map(|d| d.node().id()).collect();
+        assert_eq!(sorted, ["fn:emit", "fn:parse", "fn:lex"]);
+
+        dependencies.sort(Order::Topological);
+        let sorted: Vec<&str> =
+            dependencies.iter().map(|d| d.node().id()).collect();
+        assert_eq!(sorted, ["fn:emit", "fn:lex", "fn:parse"]);
+        // the index follows the new order
+        assert_eq!(dependencies.get("fn:parse").unwrap().level(), 1);
+        assert!(dependencies.remove("fn:lex").is_some());
+        assert_eq!(
+            dependencies.get("fn:parse").unwrap().node().id(),
+            "fn:parse"
+        );
+    }
 }
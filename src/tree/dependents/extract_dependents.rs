@@ -1,19 +1,28 @@
-
 use log;
 
+use syn::punctuated::Punctuated;
 use syn::visit::Visit;
 
-use crate::function_visitor::ItemNameVisitor;
+use crate::function_visitor::{path_to_string, ItemNameVisitor};
 use crate::types::{
-    RustEnum, RustFunction, RustStruct,
+    Identifiable, RustConst, RustEnum, RustFunction, RustMacro, RustStatic,
+    RustStruct, RustTrait, RustTypeAlias,
 };
 
-
-pub fn fn_dependency_on_target(func: &RustFunction, target_name: &str) -> bool {
+pub fn fn_dependency_on_target(
+    func: &RustFunction,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
     // get clean syntax tree via syn library by feeding it formatted code
     let func_block = func.function_block_str();
-    let syntax_tree =
-        syn::parse_file(&func_block).expect("Unable to parse code");
+    // e.g. placeholders of called functions, which have no body
+    let syntax_tree = match syn::parse_file(&func_block) {
+        Ok(syntax_tree) => syntax_tree,
+        Err(e) => {
+            log::debug!("Unable to parse function {}: {}", func.name(), e);
+            return false;
+        }
+    };
 
     let mut item_names = Vec::new();
     for item in syntax_tree.items {
@@ -23,16 +32,139 @@ pub fn fn_dependency_on_target(func: &RustFunction, target_name: &str) -> bool {
             item_names.extend(visitor.item_names);
         }
     }
+    // calls of methods (e.g. `self.run()`) need the types of self and the
+    // parameters, which the function itself knows about
+    let mut func = func.clone();
+    func.extract_function_body();
+    item_names.extend(func.instantiated_items().iter().cloned());
+
     // Check if any of the extracted names match the target name.
-    item_names.iter().any(|name| name == target_name)
+    item_names.iter().any(|name| is_target(name))
+}
+
+pub fn enum_dependency_on_target(
+    enu: &RustEnum,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    enum_item_names(enu).iter().any(|name| is_target(name))
+}
+
+pub fn struct_dependency_on_target(
+    strct: &RustStruct,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    struct_item_names(strct).iter().any(|name| is_target(name))
+}
+
+pub fn trait_dependency_on_target(
+    trt: &RustTrait,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    trait_item_names(trt).iter().any(|name| is_target(name))
+}
+
+pub fn const_dependency_on_target(
+    cnst: &RustConst,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    value_item_names(cnst.ty(), cnst.expr())
+        .iter()
+        .any(|name| is_target(name))
+}
+
+pub fn static_dependency_on_target(
+    stat: &RustStatic,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    value_item_names(stat.ty(), stat.expr())
+        .iter()
+        .any(|name| is_target(name))
+}
+
+pub fn type_alias_dependency_on_target(
+    alias: &RustTypeAlias,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    type_item_names(alias.ty())
+        .iter()
+        .any(|name| is_target(name))
+}
+
+pub fn macro_dependency_on_target(
+    mac: &RustMacro,
+    is_target: &dyn Fn(&str) -> bool,
+) -> bool {
+    macro_item_names(mac).iter().any(|name| is_target(name))
+}
+
+/// Names of types referenced by the signature of a function: parameter and
+/// return types, and generic bounds.
+pub fn fn_signature_item_names(func: &RustFunction) -> Vec<String> {
+    // get clean syntax tree via syn library by feeding it formatted code
+    let signature = format!("{} {{}}", func.signature_str());
+    let mut type_names = Vec::new();
+    match syn::parse_str::<syn::ItemFn>(&signature) {
+        Ok(item_fn) => {
+            for input in &item_fn.sig.inputs {
+                if let syn::FnArg::Typed(pat_type) = input {
+                    extract_type_names(&pat_type.ty, &mut type_names);
+                }
+            }
+            if let syn::ReturnType::Type(_, ty) = &item_fn.sig.output {
+                extract_type_names(ty, &mut type_names);
+            }
+            extract_generics_names(&item_fn.sig.generics, &mut type_names);
+        }
+        Err(e) => log::debug!("Unable to parse signature: {}", e),
+    }
+    type_names
 }
 
+/// Names of types referenced by the fields and generic bounds of a struct.
+pub fn struct_item_names(strct: &RustStruct) -> Vec<String> {
+    // get clean syntax tree via syn library by feeding it formatted code
+    let struct_fields_block = strct.struct_base_block_str();
+    let syntax_tree = match syn::parse_file(&struct_fields_block) {
+        Ok(syntax_tree) => syntax_tree,
+        Err(e) => {
+            log::debug!("Unable to parse struct {}: {}", strct.name(), e);
+            return Vec::new();
+        }
+    };
 
-pub fn enum_dependency_on_target(enu: &RustEnum, target_name: &str) -> bool {
+    let mut type_names = Vec::new();
+    for item in syntax_tree.items {
+        match item {
+            syn::Item::Struct(item_struct) => {
+                for field in item_struct.fields {
+                    extract_type_names(&field.ty, &mut type_names);
+                }
+                extract_generics_names(&item_struct.generics, &mut type_names);
+            }
+            syn::Item::Union(item_union) => {
+                for field in item_union.fields.named {
+                    extract_type_names(&field.ty, &mut type_names);
+                }
+                extract_generics_names(&item_union.generics, &mut type_names);
+            }
+            _ => log::debug!("Unexpected item in syntax tree"),
+        }
+    }
+    type_names
+}
+
+/// Names of types referenced by the variant payloads and generic bounds of
+/// an enum.
+pub fn enum_item_names(enu: &RustEnum) -> Vec<String> {
     // get clean syntax tree via syn library by feeding it formatted code
     let enum_variants_block = enu.enum_base_block_str();
-    let syntax_tree =
-        syn::parse_file(&enum_variants_block).expect("Unable to parse code");
+    let syntax_tree = match syn::parse_file(&enum_variants_block) {
+        Ok(syntax_tree) => syntax_tree,
+        Err(e) => {
+            log::debug!("Unable to parse enum {}: {}", enu.name(), e);
+            return Vec::new();
+        }
+    };
 
     let mut type_names = Vec::new();
     for item in syntax_tree.items {
@@ -42,70 +174,232 @@ pub fn enum_dependency_on_target(enu: &RustEnum, target_name: &str) -> bool {
                     extract_type_names(&field.ty, &mut type_names);
                 }
             }
+            extract_generics_names(&enum_item.generics, &mut type_names);
         } else {
-            panic!("Unexpected item in syntax tree");
+            log::debug!("Unexpected item in syntax tree");
         }
     }
-    // Check if any of the extracted type names match the target name.
-    type_names.iter().any(|name| name == target_name)
+    type_names
 }
 
+/// Names of the supertraits and generic bounds of a trait. Its methods are
+/// handled as separate functions.
+pub fn trait_item_names(trt: &RustTrait) -> Vec<String> {
+    let mut type_names = Vec::new();
+    match syn::parse_str::<syn::ItemTrait>(&trt.trait_block_str()) {
+        Ok(item_trait) => {
+            extract_bound_names(&item_trait.supertraits, &mut type_names);
+            extract_generics_names(&item_trait.generics, &mut type_names);
+        }
+        Err(e) => log::debug!("Unable to parse trait: {}", e),
+    }
+    type_names
+}
 
-pub fn struct_dependency_on_target(strct: &RustStruct, target_name: &str) -> bool {
-    // get clean syntax tree via syn library by feeding it formatted code
-    let struct_fields_block = strct.struct_base_block_str();
-    let syntax_tree =
-        syn::parse_file(&struct_fields_block).expect("Unable to parse code");
+/// Names of types and items referenced by a const or static, taken from
+/// both its type and its value expression.
+pub fn value_item_names(ty: &str, expr: &str) -> Vec<String> {
+    let mut item_names = type_item_names(ty);
+    match syn::parse_str::<syn::Expr>(expr) {
+        Ok(expr) => {
+            let mut visitor = ItemNameVisitor::new();
+            visitor.visit_expr(&expr);
+            item_names.extend(visitor.item_names);
+        }
+        Err(e) => log::debug!("Unable to parse expression: {}", e),
+    }
+    item_names
+}
 
+pub fn type_item_names(ty: &str) -> Vec<String> {
     let mut type_names = Vec::new();
-    for item in syntax_tree.items {
-        if let syn::Item::Struct(item_struct) = item {
-            for field in item_struct.fields {
-                extract_type_names(&field.ty, &mut type_names);
+    match syn::parse_str::<syn::Type>(ty) {
+        Ok(ty) => extract_type_names(&ty, &mut type_names),
+        Err(e) => log::debug!("Unable to parse type: {}", e),
+    }
+    type_names
+}
+
+/// A macro body is not parsed as Rust code, so every identifier in its
+/// rules is a potential reference.
+pub fn macro_item_names(mac: &RustMacro) -> Vec<String> {
+    let mut idents = Vec::new();
+    match mac.rules().parse::<proc_macro2::TokenStream>() {
+        Ok(tokens) => collect_idents(tokens, &mut idents),
+        Err(e) => log::debug!("Unable to tokenize macro: {}", e),
+    }
+    idents
+}
+
+// fragment specifiers of macro_rules! metavariables, e.g. `$name:ident`
+const FRAGMENT_SPECIFIERS: &[&str] = &[
+    "block",
+    "expr",
+    "expr_2021",
+    "ident",
+    "item",
+    "lifetime",
+    "literal",
+    "meta",
+    "pat",
+    "pat_param",
+    "path",
+    "stmt",
+    "tt",
+    "ty",
+    "vis",
+];
+
+fn collect_idents(tokens: proc_macro2::TokenStream, idents: &mut Vec<String>) {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            // metavariables (`$name`, `$crate`) and their fragment
+            // specifiers (`$name:ident`) are not references
+            proc_macro2::TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                if let Some(proc_macro2::TokenTree::Ident(_)) = tokens.peek() {
+                    tokens.next();
+                    skip_fragment_specifier(&mut tokens);
+                }
             }
-        } else {
-            panic!("Unexpected item in syntax tree");
+            proc_macro2::TokenTree::Ident(ident) => {
+                idents.push(ident.to_string())
+            }
+            proc_macro2::TokenTree::Group(group) => {
+                collect_idents(group.stream(), idents)
+            }
+            _ => {}
         }
     }
-    // Check if any of the extracted type names match the target name.
-    type_names.iter().any(|name| name == target_name)
+}
+
+// a `:` after a metavariable is only skipped with a known specifier, in
+// `$field: u32` the type is a reference
+fn skip_fragment_specifier(
+    tokens: &mut std::iter::Peekable<proc_macro2::token_stream::IntoIter>,
+) {
+    let mut lookahead = tokens.clone();
+    let is_colon = matches!(
+        lookahead.next(),
+        Some(proc_macro2::TokenTree::Punct(punct)) if punct.as_char() == ':'
+    );
+    let is_specifier = matches!(
+        lookahead.next(),
+        Some(proc_macro2::TokenTree::Ident(ident))
+            if FRAGMENT_SPECIFIERS.contains(&ident.to_string().as_str())
+    );
+    if is_colon && is_specifier {
+        tokens.next();
+        tokens.next();
+    }
 }
 
 fn extract_type_names(ty: &syn::Type, type_names: &mut Vec<String>) {
     match ty {
         syn::Type::Path(type_path) => {
-            if let Some(segment) = type_path.path.segments.last() {
-                type_names.push(segment.ident.to_string());
-                // Recursively handle nested types
-                if let syn::PathArguments::AngleBracketed(
-                    angle_bracketed_args,
-                ) = &segment.arguments
-                {
-                    for arg in &angle_bracketed_args.args {
-                        match arg {
-                            syn::GenericArgument::Type(nested_ty) => {
-                                extract_type_names(nested_ty, type_names);
-                            }
-                            _ => {
-                                log::debug!(
-                                    "Unexpected generic argument -- not yet \
-                                     supported"
-                                );
-                            }
-                        }
-                    }
-                }
-            }
+            extract_path_names(&type_path.path, type_names);
         }
         syn::Type::Tuple(type_tuple) => {
             for elem_ty in &type_tuple.elems {
                 extract_type_names(elem_ty, type_names);
             }
         }
+        // e.g. `&'static [Entry]` in const tables
+        syn::Type::Reference(type_ref) => {
+            extract_type_names(&type_ref.elem, type_names);
+        }
+        syn::Type::Slice(type_slice) => {
+            extract_type_names(&type_slice.elem, type_names);
+        }
+        syn::Type::Array(type_array) => {
+            extract_type_names(&type_array.elem, type_names);
+        }
+        syn::Type::Ptr(type_ptr) => {
+            extract_type_names(&type_ptr.elem, type_names);
+        }
+        syn::Type::Paren(type_paren) => {
+            extract_type_names(&type_paren.elem, type_names);
+        }
+        // `impl Trait` and `dyn Trait`
+        syn::Type::ImplTrait(type_impl) => {
+            extract_bound_names(&type_impl.bounds, type_names);
+        }
+        syn::Type::TraitObject(type_object) => {
+            extract_bound_names(&type_object.bounds, type_names);
+        }
         _ => {
-            // Path and Tuple catches majority of syntax for now,
-            // but should expand this to handle any valid case over time
+            // remaining types (e.g. fn pointers, macros) are rare in
+            // signatures, but should expand this over time
             log::debug!("Unexpected type -- not yet supported");
         }
     }
 }
+
+// the path itself, and types in its generic arguments
+fn extract_path_names(path: &syn::Path, type_names: &mut Vec<String>) {
+    let Some(segment) = path.segments.last() else {
+        return;
+    };
+    type_names.push(path_to_string(path));
+    // Recursively handle nested types
+    if let syn::PathArguments::AngleBracketed(angle_bracketed_args) =
+        &segment.arguments
+    {
+        for arg in &angle_bracketed_args.args {
+            match arg {
+                syn::GenericArgument::Type(nested_ty) => {
+                    extract_type_names(nested_ty, type_names);
+                }
+                // e.g. `Iterator<Item = Entry>`
+                syn::GenericArgument::AssocType(assoc_type) => {
+                    extract_type_names(&assoc_type.ty, type_names);
+                }
+                _ => {
+                    log::debug!(
+                        "Unexpected generic argument -- not yet supported"
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn extract_bound_names(
+    bounds: &Punctuated<syn::TypeParamBound, syn::Token![+]>,
+    type_names: &mut Vec<String>,
+) {
+    for bound in bounds {
+        if let syn::TypeParamBound::Trait(trait_bound) = bound {
+            extract_path_names(&trait_bound.path, type_names);
+        }
+    }
+}
+
+// bounds and defaults of generic parameters, and where clause predicates
+fn extract_generics_names(
+    generics: &syn::Generics,
+    type_names: &mut Vec<String>,
+) {
+    for param in &generics.params {
+        match param {
+            syn::GenericParam::Type(type_param) => {
+                extract_bound_names(&type_param.bounds, type_names);
+                if let Some(default) = &type_param.default {
+                    extract_type_names(default, type_names);
+                }
+            }
+            syn::GenericParam::Const(const_param) => {
+                extract_type_names(&const_param.ty, type_names);
+            }
+            syn::GenericParam::Lifetime(_) => {}
+        }
+    }
+    if let Some(where_clause) = &generics.where_clause {
+        for predicate in &where_clause.predicates {
+            if let syn::WherePredicate::Type(predicate_type) = predicate {
+                extract_type_names(&predicate_type.bounded_ty, type_names);
+                extract_bound_names(&predicate_type.bounds, type_names);
+            }
+        }
+    }
+}
@@ -1,87 +1,187 @@
-
-use crate::tree::RootNode;
-use crate::types::{
-    Identifiable, RustType,
-};
-use super::Dependents;
 use super::extract_dependents::*;
+use super::Dependent;
+use crate::tree::{NameResolver, Resolution, RootNode, TreeNode};
+use crate::types::{Identifiable, RustType};
+
+/// An item to find the dependents of. Besides a root level item this can
+/// be a single method, which is referred to as `Type::method`.
+#[derive(Clone)]
+pub struct DependentTarget {
+    item: RustType,
+    // qualified path of the item, for methods that of the owning type
+    path: String,
+    method: Option<String>,
+    // ids of the items from this one up to the queried item
+    chain: Vec<String>,
+}
+
+impl DependentTarget {
+    pub fn new(item: RustType, path: String) -> Self {
+        let chain = vec![item.id().to_string()];
+        DependentTarget {
+            item,
+            path,
+            method: None,
+            chain,
+        }
+    }
 
+    /// A method of the type at `type_path`.
+    pub fn method(item: RustType, type_path: String) -> Self {
+        let method = Some(item.name().to_string());
+        DependentTarget {
+            method,
+            ..DependentTarget::new(item, type_path)
+        }
+    }
+
+    pub fn item(&self) -> &RustType {
+        &self.item
+    }
+
+    pub fn chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    fn is_referenced_by(
+        &self,
+        resolver: &NameResolver,
+        module_path: &[String],
+        path: &str,
+    ) -> bool {
+        let segments: Vec<&str> = path.split("::").collect();
+        let name = segments.last().copied();
+        match &self.method {
+            // associated items resolve to their type, so the method name
+            // has to match as well
+            Some(method) => {
+                if segments.len() < 2 || name != Some(method.as_str()) {
+                    return false;
+                }
+                match resolver.resolve(module_path, path) {
+                    Resolution::Item(item) => item == self.path,
+                    Resolution::External => false,
+                    // fall back to matching by type name
+                    Resolution::Unknown => {
+                        self.path.rsplit("::").next()
+                            == Some(segments[segments.len() - 2])
+                    }
+                }
+            }
+            None => match resolver.resolve(module_path, path) {
+                Resolution::Item(item) => item == self.path,
+                Resolution::External => false,
+                // fall back to matching by name
+                Resolution::Unknown => name == Some(self.item.name()),
+            },
+        }
+    }
+}
+
+/// Register the items of `root_node` that refer to `target` as its
+/// dependents, and return them as targets for the next level.
 pub fn find_dependents(
     root_node: &mut RootNode,
-    target_item: &dyn Identifiable,
-    _filter_path: Vec<&str>,
-) {
-    let mut dependents = Dependents::default();
-    let source = root_node.file_path().real_path();
+    target: &DependentTarget,
+    resolver: &NameResolver,
+) -> Vec<DependentTarget> {
+    let mut found_items = Vec::new();
+    let source = root_node.file_path().relative_path();
+    let module_path = root_node.module_path().clone();
+
+    // a reference only counts if it resolves to the target itself
+    let is_target =
+        |path: &str| target.is_referenced_by(resolver, &module_path, path);
 
-    for node in root_node.children_mut().iter_mut() {
+    for node in root_node.children().iter() {
         let rust_item = node.rtype();
 
         let items_found =
-            find_identifiable_items(Box::new(rust_item.clone()), target_item);
-        if !items_found.is_empty() {
-            // TODO: for now register complete item if a dependency is found.
-            // ideally we should register only parts that have the
-            // target_item as a direct dependency (example: a method of a
-            // struct instead of a full struct).
-            log::debug!(
-                "Found {} dependents for {}",
-                items_found.len(),
-                target_item.name()
-            );
-            dependents.register_item(
-                node.clone(), // TODO: why not register RustItem instead
-                Some(&source),
-            );
+            find_identifiable_items(rust_item, target.item(), &is_target);
+        log::debug!(
+            "Found {} dependents for {} in {}",
+            items_found.len(),
+            target.item().name(),
+            rust_item.name()
+        );
+        // only the method referring to the target is registered, not the
+        // whole type it belongs to
+        let item_path =
+            format!("{}::{}", module_path.join("::"), rust_item.name());
+        for item in items_found {
+            // a cycle back to an item of the chain, e.g. recursion
+            if target.chain().iter().any(|id| id == item.id()) {
+                continue;
+            }
+            let (mut found, owner) = if item.id() == rust_item.id() {
+                (DependentTarget::new(item, item_path.clone()), None)
+            } else {
+                (
+                    DependentTarget::method(item, item_path.clone()),
+                    Some(rust_item.clone()),
+                )
+            };
+            found.chain.extend(target.chain().iter().cloned());
+            found_items.push((found, owner));
         }
     }
-    root_node.set_dependents(dependents);
+
+    // dependents of earlier targets (e.g. for a pattern query) are kept
+    let dependents = root_node.dependents_mut();
+    let mut found_targets = Vec::new();
+    for (found, owner) in found_items {
+        dependents.register_item(Dependent::new(
+            TreeNode::new(found.item().clone()),
+            Some(&source),
+            found.chain().to_vec(),
+            owner,
+        ));
+        found_targets.push(found);
+    }
+    found_targets
 }
 
 fn find_identifiable_items(
-    item: Box<dyn Identifiable>,
-    target_item: &dyn Identifiable,
-) -> Vec<Box<dyn Identifiable>> {
+    rust_type: &RustType,
+    target_item: &RustType,
+    is_target: &dyn Fn(&str) -> bool,
+) -> Vec<RustType> {
     // item cant depend on itself
-    if item.id() == target_item.id() {
+    if rust_type.id() == target_item.id() {
         return vec![];
     }
     let mut dependent_items = Vec::new();
-    if let Some(rust_type) = item.as_rust_type() {
-        // impl methods on each type (struct, enum, trait), and nested
-        // functions can be processed as a RustFunction
-        // call this function recursively for each
-        if let Some(methods) = rust_type.methods() {
-            for method in methods {
-                dependent_items.extend(find_identifiable_items(
-                    Box::new(RustType::Function(method.clone())),
-                    target_item,
-                ));
-            }
+    // impl methods on each type (struct, enum, trait) can be processed as
+    // a RustFunction, the methods of a function are the ones it calls
+    if !matches!(rust_type, RustType::Function(_)) {
+        for method in rust_type.methods().into_iter().flatten() {
+            dependent_items.extend(find_identifiable_items(
+                &RustType::Function(Box::new(method.clone())),
+                target_item,
+                is_target,
+            ));
         }
+    }
 
-        // check item for dependency on target
-        // note excluding (impl) methods as this is already done
-        let is_dependent = match rust_type {
-            RustType::Function(func) => {
-                fn_dependency_on_target(func, target_item.name())
-            }
-            RustType::Struct(strct) => {
-                struct_dependency_on_target(strct, target_item.name())
-            }
-            RustType::Enum(enu) => {
-                enum_dependency_on_target(enu, target_item.name())
-            }
-            // Trait should not have additinonal dependents
-            // (methods are already checked as functions above)
-            RustType::Trait(_) => false,
-        };
-        if is_dependent {
-            dependent_items.push(item);
+    // check item for dependency on target
+    // note excluding (impl) methods as this is already done
+    let is_dependent = match rust_type {
+        RustType::Function(func) => fn_dependency_on_target(func, is_target),
+        RustType::Struct(strct) => {
+            struct_dependency_on_target(strct, is_target)
+        }
+        RustType::Enum(enu) => enum_dependency_on_target(enu, is_target),
+        // methods are already checked as functions above
+        RustType::Trait(trt) => trait_dependency_on_target(trt, is_target),
+        RustType::Const(cnst) => const_dependency_on_target(cnst, is_target),
+        RustType::Static(stat) => static_dependency_on_target(stat, is_target),
+        RustType::TypeAlias(alias) => {
+            type_alias_dependency_on_target(alias, is_target)
         }
-    } else {
-        panic!("item is not a RustType: {:?}", item.name());
+        RustType::Macro(mac) => macro_dependency_on_target(mac, is_target),
+    };
+    if is_dependent {
+        dependent_items.push(rust_type.clone());
     }
     dependent_items
 }
-
@@ -1,86 +1,189 @@
 mod find_dependents;
 mod extract_dependents;
 
-pub use find_dependents::find_dependents;
+pub use find_dependents::{find_dependents, DependentTarget};
+pub(crate) use extract_dependents::{
+    enum_item_names, fn_signature_item_names, macro_item_names,
+    struct_item_names, trait_item_names, type_item_names, value_item_names,
+};
 
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use log;
-use crate::tree::TreeNode;
-use crate::types::RustType;
+use crate::helpers::source_location;
+use crate::tree::{Order, TreeNode};
+use crate::types::{CodeStyle, RustType};
 use crate::writers::ClippyWriter;
 
 
+#[derive(Default)]
 pub struct Dependents {
-    items_by_id: HashMap<String, Dependent>,
+    // in the order they are printed, see `sort`
+    items: Vec<Dependent>,
+    index_by_id: HashMap<String, usize>,
 }
 
-impl Default for Dependents {
-    fn default() -> Self {
-        Dependents {
-            items_by_id: HashMap::new(),
+impl Dependents {
+    /// An item reached through more than one chain keeps the first, which
+    /// is the shortest.
+    pub fn register_item(&mut self, dependent: Dependent) {
+        log::debug!("adding dependent: {}", dependent.node().name());
+        let node_id = dependent.node().id().to_string();
+        if !self.index_by_id.contains_key(&node_id) {
+            self.index_by_id.insert(node_id, self.items.len());
+            self.items.push(dependent);
         }
     }
-}
 
-impl Dependents {
-    pub fn register_item(&mut self, node: TreeNode, source: Option<&str>) {
-        log::debug!("adding dependent: {}", node.clone().name());
-        let node_id = node.id().to_string();
-        let dependent = Dependent::new(node, source);
-        self.items_by_id.insert(node_id, dependent);
+    pub fn iter(&self) -> impl Iterator<Item = &Dependent> {
+        self.items.iter()
     }
 
-    pub fn len(&self) -> usize {
-        self.items_by_id.len()
+    pub fn get_mut(&mut self, id: &str) -> Option<&mut Dependent> {
+        self.index_by_id.get(id).map(|&index| &mut self.items[index])
     }
 
-    pub fn print(&self, writer: &mut Box<dyn ClippyWriter>) {
-        for (_, dependent) in &self.items_by_id {
-            self.print_dependent(writer, dependent);
-        }
+    pub fn remove(&mut self, id: &str) -> Option<Dependent> {
+        let index = self.index_by_id.remove(id)?;
+        let dependent = self.items.remove(index);
+        self.reindex();
+        Some(dependent)
     }
 
-    fn print_dependent(
-        &self,
-        writer: &mut Box<dyn ClippyWriter>,
-        dependent: &Dependent,
-    ) {
-        let _ = writeln!(writer, "@{}:", dependent.source().unwrap_or(""),);
+    /// Sort the dependents in `order`. In topological order the direct
+    /// dependents come first, then theirs and so on.
+    pub fn sort(&mut self, order: Order) {
+        self.items.sort_by(|a, b| {
+            let by_level = match order {
+                Order::Topological => a.chain().len().cmp(&b.chain().len()),
+                _ => Ordering::Equal,
+            };
+            by_level.then_with(|| {
+                order.compare((a.node(), a.source()), (b.node(), b.source()))
+            })
+        });
+        self.reindex();
+    }
 
-        match dependent.node().rtype() {
-            RustType::Function(rust_function) => {
-                let _ = write!(writer, "{}", rust_function);
-            }
-            RustType::Struct(rust_struct) => {
-                let _ = write!(writer, "{}", rust_struct);
-            }
-            RustType::Enum(rust_enum) => {
-                let _ = write!(writer, "{}", rust_enum);
-            }
-            RustType::Trait(rust_trait) => {
-                let _ = write!(writer, "{}", rust_trait);
-            }
-        }
+    fn reindex(&mut self) {
+        self.index_by_id = self
+            .items
+            .iter()
+            .enumerate()
+            .map(|(index, dependent)| {
+                (dependent.node().id().to_string(), index)
+            })
+            .collect();
     }
 }
 
 pub struct Dependent {
     node: TreeNode,
     source: Option<String>,
+    chain: Vec<String>,
+    // type the method belongs to, if the dependent is a method
+    owner: Option<RustType>,
 }
 
 impl Dependent {
-    pub fn new(node: TreeNode, source: Option<&str>) -> Self {
+    pub fn new(
+        node: TreeNode,
+        source: Option<&str>,
+        chain: Vec<String>,
+        owner: Option<RustType>,
+    ) -> Self {
         Dependent {
             node,
             source: source.map(|s| s.to_string()),
+            chain,
+            owner,
         }
     }
 
     pub fn node(&self) -> &TreeNode {
         &self.node
     }
+
+    pub fn node_mut(&mut self) -> &mut TreeNode {
+        &mut self.node
+    }
+
     pub fn source(&self) -> Option<&str> {
-        self.source.as_ref().map(|s| s.as_str())
+        self.source.as_deref()
+    }
+
+    /// Ids from this item up to the queried item, e.g. the caller of a
+    /// function calling the queried one.
+    pub fn chain(&self) -> &[String] {
+        &self.chain
+    }
+
+    pub fn owner(&self) -> Option<&RustType> {
+        self.owner.as_ref()
+    }
+
+    /// The item preceded by its `@file:line:` header, its id if `show_ids`
+    /// is set, and the chain leading to the queried item if indirect. See
+    /// `print_code` for `show_owners`.
+    pub fn print(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        show_ids: bool,
+        show_owners: bool,
+        code_style: CodeStyle,
+    ) {
+        let location = source_location(
+            self.source().unwrap_or(""),
+            self.node().rtype().span(),
+        );
+        writer.start_group(self.source().unwrap_or(""));
+        writer.start_item(self.node().id());
+        let _ = writeln!(writer, "@{}:", location);
+        if show_ids {
+            let _ = writeln!(writer, "// id: {}", self.node().id());
+        }
+        // only indirect dependents need an explanation
+        if self.chain().len() > 2 {
+            let _ = writeln!(
+                writer,
+                "// used by chain: {}",
+                self.chain().join(" -> ")
+            );
+        }
+
+        self.print_code(writer, show_owners, code_style);
+    }
+
+    /// The item itself, without any header. With `show_owners`, a method
+    /// is preceded by the definition (fields or variants, no methods) of
+    /// the type it belongs to.
+    pub fn print_code(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        show_owners: bool,
+        code_style: CodeStyle,
+    ) {
+        let code_style = self.node.code_style(code_style);
+        if show_owners {
+            let definition = match self.owner() {
+                Some(RustType::Struct(rust_struct)) => {
+                    rust_struct.definition_code(code_style)
+                }
+                Some(RustType::Enum(rust_enum)) => {
+                    rust_enum.definition_code(code_style)
+                }
+                // the impl header of the method covers the rest
+                _ => String::new(),
+            };
+            let _ = write!(writer, "{}", definition);
+        }
+
+        match self.node().rtype() {
+            // a method shows the impl block it is part of
+            RustType::Function(rust_function) => {
+                let code = rust_function.impl_block_code(code_style);
+                let _ = write!(writer, "{}", code);
+            }
+            rtype => rtype.print_code(writer, code_style),
+        }
     }
 }
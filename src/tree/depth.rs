@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+/// How far to follow the dependency graph from the queried items, e.g.
+/// `--depth 2` also includes the dependencies of direct dependencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Depth {
+    Levels(usize),
+    /// Follow until every reachable item is included.
+    All,
+}
+
+impl Default for Depth {
+    fn default() -> Self {
+        Depth::Levels(1)
+    }
+}
+
+impl Depth {
+    /// Whether items `level` hops away from the queried items are included.
+    pub fn includes(&self, level: usize) -> bool {
+        match self {
+            Depth::Levels(levels) => level <= *levels,
+            Depth::All => true,
+        }
+    }
+}
+
+impl FromStr for Depth {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Depth::All),
+            _ => match s.parse::<usize>() {
+                Ok(levels) if levels > 0 => Ok(Depth::Levels(levels)),
+                _ => Err(format!("'{}' is not a positive number or 'all'", s)),
+            },
+        }
+    }
+}
@@ -20,13 +20,22 @@ impl<'a> ChunkInitializer<'a> {
         &mut self,
         global_registry: &mut GlobalRegistry,
     ) -> RootNode {
-        let mut root = RootNode::new(self.visitor.file_path().clone());
+        let mut root = RootNode::new(
+            self.visitor.file_path().clone(),
+            self.visitor.module_path().clone(),
+            self.visitor.inline_path().clone(),
+            self.visitor.use_declarations.clone(),
+        );
         let mut visited = HashSet::new();
 
         self.add_functions(&mut root, &mut visited);
-        self.add_structs(&mut root, global_registry, &mut visited);
+        self.add_structs(&mut root, &mut visited);
         self.add_enums(&mut root);
         self.add_traits(&mut root);
+        self.add_consts_and_statics(&mut root);
+        self.add_type_aliases(&mut root);
+        self.add_macros(&mut root);
+        self.register_public_items(&root, global_registry);
 
         root
     }
@@ -45,24 +54,13 @@ impl<'a> ChunkInitializer<'a> {
         }
     }
 
-    fn add_structs(
-        &mut self,
-        root: &mut RootNode,
-        global_registry: &mut GlobalRegistry,
-        visited: &mut HashSet<String>,
-    ) {
+    fn add_structs(&self, root: &mut RootNode, visited: &mut HashSet<String>) {
         for rust_struct in &self.visitor.structs {
             root.add_child(create_struct_node(
                 self.visitor,
                 rust_struct,
                 visited,
             ));
-            if rust_struct.is_public() {
-                global_registry.register_struct(
-                    rust_struct.clone(),
-                    Some(root.file_path().relative_path().as_str()),
-                );
-            }
         }
     }
 
@@ -77,6 +75,50 @@ impl<'a> ChunkInitializer<'a> {
             root.add_child(create_trait_node(rust_trait));
         }
     }
+
+    fn add_consts_and_statics(&self, root: &mut RootNode) {
+        for rust_const in &self.visitor.consts {
+            root.add_child(TreeNode::new(RustType::Const(rust_const.clone())));
+        }
+        for rust_static in &self.visitor.statics {
+            root.add_child(TreeNode::new(RustType::Static(
+                rust_static.clone(),
+            )));
+        }
+    }
+
+    fn add_type_aliases(&self, root: &mut RootNode) {
+        for rust_type_alias in &self.visitor.type_aliases {
+            root.add_child(TreeNode::new(RustType::TypeAlias(
+                rust_type_alias.clone(),
+            )));
+        }
+    }
+
+    fn add_macros(&self, root: &mut RootNode) {
+        for rust_macro in &self.visitor.macros {
+            root.add_child(TreeNode::new(RustType::Macro(rust_macro.clone())));
+        }
+    }
+
+    // public items can be referred to from any file, impls are registered
+    // regardless as their methods may be public
+    fn register_public_items(
+        &self,
+        root: &RootNode,
+        global_registry: &mut GlobalRegistry,
+    ) {
+        let source = root.file_path().relative_path();
+        for child in root.children() {
+            if child.rtype().is_public() {
+                global_registry
+                    .register_item(child.rtype().clone(), Some(&source));
+            }
+        }
+        for rust_impl in &self.visitor.impls {
+            global_registry.register_impl(rust_impl.clone(), Some(&source));
+        }
+    }
 }
 
 fn create_function_node(
@@ -84,7 +126,7 @@ fn create_function_node(
     func: &RustFunction,
     visited: &mut HashSet<String>,
 ) -> TreeNode {
-    let mut node = TreeNode::new(RustType::Function(func.clone()));
+    let mut node = TreeNode::new(RustType::Function(Box::new(func.clone())));
     for called_method in func.methods().unwrap_or(&vec![]) {
         node.add_child(create_function_node(visitor, called_method, visited));
     }
@@ -141,7 +183,8 @@ fn create_enum_node(e: &RustEnum) -> TreeNode {
 fn create_trait_node(t: &RustTrait) -> TreeNode {
     let mut node = TreeNode::new(RustType::Trait(t.clone()));
     for method in t.methods().unwrap_or(&Vec::new()) {
-        let method_node = TreeNode::new(RustType::Function(method.clone()));
+        let method_node =
+            TreeNode::new(RustType::Function(Box::new(method.clone())));
         node.add_child(method_node);
     }
     node
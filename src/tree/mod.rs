@@ -1,12 +1,18 @@
 mod builder;
 mod dependencies;
+mod depth;
 mod dependents;
 mod initialize;
+mod order;
+mod resolver;
 mod root;
 mod tree;
 
 pub use builder::TreeBuilder;
-pub use dependencies::Dependencies;
-pub use dependents::{Dependents, find_dependents};
+pub use dependencies::{Dependencies, Dependency};
+pub use depth::Depth;
+pub use dependents::{Dependent, Dependents, find_dependents};
+pub use order::Order;
+pub use resolver::{absolute_path, NameResolver, Resolution};
 pub use root::RootNode;
 pub use tree::TreeNode;
@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use crate::tree::TreeNode;
+
+/// Order of the items in the output, e.g. `--order topo` lists each
+/// dependency before the items requiring it. Files are always listed by
+/// path, queried items within a file and dependents are ordered too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Order {
+    /// By the path of the item, e.g. `crate::map::list_map`.
+    #[default]
+    Path,
+    /// By source file, then line.
+    Position,
+    /// Dependencies before the items requiring them, dependents after the
+    /// items they depend on. Ties and cycles fall back to the position.
+    Topological,
+}
+
+impl Order {
+    /// Compare two items by path or position. Topological order is not a
+    /// relation between two items alone, it is compared by position here.
+    pub fn compare(
+        self,
+        (node, source): (&TreeNode, Option<&str>),
+        (other, other_source): (&TreeNode, Option<&str>),
+    ) -> Ordering {
+        match self {
+            Order::Path => item_path(node.id())
+                .cmp(item_path(other.id()))
+                .then_with(|| node.id().cmp(other.id())),
+            Order::Position | Order::Topological => source
+                .cmp(&other_source)
+                .then_with(|| line_column(node).cmp(&line_column(other)))
+                .then_with(|| node.id().cmp(other.id())),
+        }
+    }
+}
+
+impl FromStr for Order {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "path" => Ok(Order::Path),
+            "position" => Ok(Order::Position),
+            "topo" => Ok(Order::Topological),
+            _ => Err(format!(
+                "'{}' is not one of 'path', 'position' or 'topo'",
+                s
+            )),
+        }
+    }
+}
+
+// the path of an id is the part after its kind, see `generate_id`
+fn item_path(id: &str) -> &str {
+    id.split_once(':').map_or(id, |(_, path)| path)
+}
+
+// items without a span are listed first
+fn line_column(node: &TreeNode) -> Option<(usize, usize)> {
+    node.rtype()
+        .span()
+        .map(|span| (span.start.line, span.start.column))
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::LineColumn;
+
+    use super::*;
+    use crate::types::{
+        RustFunction, RustStruct, RustType, Span, StructKind, Visibility,
+    };
+
+    fn node(module: &str, name: &str, line: usize) -> TreeNode {
+        let mut function = RustFunction::new_with_data(
+            name,
+            Visibility::Public,
+            Vec::new(),
+            None,
+            None,
+            None,
+        );
+        function.qualify_id(module);
+        let start = LineColumn { line, column: 0 };
+        function.set_span(Span::new(start, start));
+        TreeNode::new(RustType::Function(Box::new(function)))
+    }
+
+    fn sorted(
+        order: Order,
+        items: &[(&TreeNode, Option<&str>)],
+    ) -> Vec<String> {
+        let mut items = items.to_vec();
+        items.sort_by(|a, b| order.compare(*a, *b));
+        items
+            .iter()
+            .map(|(node, _)| node.id().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn orders_by_path_or_position() {
+        let list_map = node("demo::map", "list_map", 30);
+        let handle_ls = node("demo::cli", "handle_ls", 10);
+        let print_text = node("demo::map", "print_text", 20);
+        let items = [
+            (&list_map, Some("map.rs")),
+            (&handle_ls, Some("cli.rs")),
+            (&print_text, Some("map.rs")),
+        ];
+
+        assert_eq!(
+            sorted(Order::Path, &items),
+            [
+                "fn:demo::cli::handle_ls",
+                "fn:demo::map::list_map",
+                "fn:demo::map::print_text"
+            ]
+        );
+        assert_eq!(
+            sorted(Order::Position, &items),
+            [
+                "fn:demo::cli::handle_ls",
+                "fn:demo::map::print_text",
+                "fn:demo::map::list_map"
+            ]
+        );
+        assert_eq!(
+            sorted(Order::Topological, &items),
+            sorted(Order::Position, &items)
+        );
+    }
+
+    #[test]
+    fn path_ignores_the_kind() {
+        let mut rust_struct = RustStruct::new(
+            "struct:demo::Config",
+            Visibility::Public,
+            "Config",
+            StructKind::Unit,
+        );
+        rust_struct.set_span(Span::new(
+            LineColumn { line: 1, column: 0 },
+            LineColumn { line: 1, column: 0 },
+        ));
+        let config = TreeNode::new(RustType::Struct(rust_struct));
+        let build = node("demo", "build", 5);
+        let items = [(&build, None), (&config, None)];
+        // `struct:` would sort after `fn:`
+        assert_eq!(
+            sorted(Order::Path, &items),
+            ["struct:demo::Config", "fn:demo::build"]
+        );
+    }
+
+    #[test]
+    fn parses_order_names() {
+        assert_eq!("path".parse::<Order>(), Ok(Order::Path));
+        assert_eq!("position".parse::<Order>(), Ok(Order::Position));
+        assert_eq!("topo".parse::<Order>(), Ok(Order::Topological));
+        assert!("size".parse::<Order>().is_err());
+    }
+}
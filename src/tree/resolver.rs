@@ -0,0 +1,476 @@
+use std::collections::{HashMap, HashSet};
+
+use super::{RootNode, TreeNode};
+
+// guards against `use` cycles, e.g. two modules glob importing each other
+const MAX_RESOLVE_DEPTH: usize = 8;
+
+/// Outcome of resolving a path as written in the code.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resolution {
+    /// Fully qualified path of an item, e.g. `codeclippy::tree::RootNode`.
+    Item(String),
+    /// The path points into another crate (e.g. `std`).
+    External,
+    /// Could be a local variable, a generic, a prelude item or an item in
+    /// a file that was not scanned.
+    Unknown,
+}
+
+struct ModuleScope {
+    items: HashSet<String>,
+    // local name -> absolute path, e.g. Baz -> codeclippy::foo::Bar
+    imports: HashMap<String, Vec<String>>,
+    // absolute paths of modules (or enums) imported with `::*`
+    globs: Vec<Vec<String>>,
+}
+
+/// Resolves paths to items across all root nodes, taking `use`
+/// declarations (including renames, globs and `self`/`super`/`crate`
+/// prefixes) of the referring module into account.
+pub struct NameResolver {
+    modules: HashMap<String, ModuleScope>,
+    nodes: HashMap<String, (TreeNode, String)>,
+    // names of the scanned crates, e.g. a binary can refer to its library
+    crates: HashSet<String>,
+}
+
+impl NameResolver {
+    pub fn new(root_nodes: &[RootNode]) -> Self {
+        let module_paths: HashSet<String> = root_nodes
+            .iter()
+            .map(|root| root.module_path().join("::"))
+            .collect();
+
+        let crates: HashSet<String> = root_nodes
+            .iter()
+            .filter_map(|root| root.module_path().first().cloned())
+            .collect();
+        let mut modules: HashMap<String, ModuleScope> = HashMap::new();
+        let mut nodes = HashMap::new();
+
+        for root in root_nodes {
+            let module_path = root.module_path().join("::");
+            let scope =
+                modules.entry(module_path.clone()).or_insert_with(|| {
+                    ModuleScope {
+                        items: HashSet::new(),
+                        imports: HashMap::new(),
+                        globs: Vec::new(),
+                    }
+                });
+
+            for child in root.children() {
+                scope.items.insert(child.name().to_string());
+                nodes.insert(
+                    format!("{}::{}", module_path, child.name()),
+                    (child.clone(), root.file_path().relative_path()),
+                );
+            }
+
+            for declaration in root.use_declarations() {
+                let path = absolute_use_path(
+                    root.module_path(),
+                    &declaration.path,
+                    &module_paths,
+                    root.children(),
+                );
+                match &declaration.alias {
+                    Some(alias) => {
+                        scope.imports.insert(alias.clone(), path);
+                    }
+                    None => scope.globs.push(path),
+                }
+            }
+        }
+
+        NameResolver {
+            modules,
+            nodes,
+            crates,
+        }
+    }
+
+    /// Resolve `path` (e.g. `Bar`, `super::Bar` or `Bar::new`) as written
+    /// in the module `module_path`.
+    pub fn resolve(&self, module_path: &[String], path: &str) -> Resolution {
+        let segments: Vec<String> =
+            path.split("::").map(ToString::to_string).collect();
+        let Some(first) = segments.first() else {
+            return Resolution::Unknown;
+        };
+
+        let absolute_path = match first.as_str() {
+            // leading `::`
+            "" => return Resolution::External,
+            // needs the impl block as context
+            "Self" => return Resolution::Unknown,
+            "crate" | "self" | "super" => {
+                match relative_path(module_path, &segments) {
+                    Some(absolute_path) => absolute_path,
+                    None => return Resolution::Unknown,
+                }
+            }
+            _ if segments.len() == 1 => {
+                return self
+                    .lookup_name(module_path, first, 0)
+                    .unwrap_or(Resolution::Unknown);
+            }
+            _ => {
+                let mut absolute_path =
+                    match self.lookup_module_prefix(module_path, first) {
+                        Some(prefix) => prefix,
+                        // neither a local item nor an import, e.g. std::fmt
+                        None => return Resolution::External,
+                    };
+                absolute_path.extend(segments[1..].iter().cloned());
+                absolute_path
+            }
+        };
+        self.lookup_absolute(&absolute_path, 0)
+    }
+
+    /// Node and source file of a resolved item.
+    pub fn node(&self, qualified_path: &str) -> Option<(&TreeNode, &str)> {
+        self.nodes
+            .get(qualified_path)
+            .map(|(node, source)| (node, source.as_str()))
+    }
+
+    // where the first segment of a multi-segment path points to
+    fn lookup_module_prefix(
+        &self,
+        module_path: &[String],
+        name: &str,
+    ) -> Option<Vec<String>> {
+        let mut local_path = module_path.to_vec();
+        local_path.push(name.to_string());
+        if self.modules.contains_key(&local_path.join("::")) {
+            return Some(local_path);
+        }
+        let scope = self.modules.get(&module_path.join("::"))?;
+        if scope.items.contains(name) {
+            return Some(local_path);
+        }
+        if let Some(import) = scope.imports.get(name) {
+            return Some(import.clone());
+        }
+        match self.lookup_name(module_path, name, 0) {
+            Some(Resolution::Item(item)) => {
+                Some(item.split("::").map(ToString::to_string).collect())
+            }
+            // e.g. `codeclippy::tree::RootNode` in a binary of codeclippy
+            _ if self.crates.contains(name) => Some(vec![name.to_string()]),
+            _ => None,
+        }
+    }
+
+    fn lookup_name(
+        &self,
+        module_path: &[String],
+        name: &str,
+        depth: usize,
+    ) -> Option<Resolution> {
+        if depth > MAX_RESOLVE_DEPTH {
+            return None;
+        }
+        let module_key = module_path.join("::");
+        let scope = self.modules.get(&module_key)?;
+
+        if scope.items.contains(name) {
+            return Some(Resolution::Item(format!("{}::{}", module_key, name)));
+        }
+        if let Some(import) = scope.imports.get(name) {
+            return Some(self.lookup_absolute(import, depth + 1));
+        }
+        scope.globs.iter().find_map(|glob| {
+            self.lookup_name(glob, name, depth + 1)
+                .filter(|resolution| *resolution != Resolution::External)
+        })
+    }
+
+    fn lookup_absolute(&self, path: &[String], depth: usize) -> Resolution {
+        if !path
+            .first()
+            .is_some_and(|first| self.crates.contains(first))
+        {
+            return Resolution::External;
+        }
+        // drop trailing segments to resolve associated items, e.g.
+        // codeclippy::config::Config::new resolves to
+        // codeclippy::config::Config
+        for end in (2..=path.len()).rev() {
+            let (module_path, name) = (&path[..end - 1], &path[end - 1]);
+            if let Some(Resolution::Item(item)) =
+                self.lookup_name(module_path, name, depth)
+            {
+                return Resolution::Item(item);
+            }
+        }
+        Resolution::Unknown
+    }
+}
+
+/// `path` as written in the module `module_path`, from the crate root,
+/// e.g. `super::Bar` in `codeclippy::foo::baz` is `codeclippy::foo::Bar`.
+/// A path without `crate`, `self` or `super` is taken to be relative to
+/// the module.
+pub fn absolute_path(module_path: &[String], path: &str) -> String {
+    let segments: Vec<String> =
+        path.split("::").map(ToString::to_string).collect();
+    let absolute_path = match segments.first().map(String::as_str) {
+        Some("crate" | "self" | "super") => {
+            relative_path(module_path, &segments).unwrap_or(segments)
+        }
+        _ => module_path.iter().cloned().chain(segments).collect(),
+    };
+    absolute_path.join("::")
+}
+
+fn relative_path(
+    module_path: &[String],
+    segments: &[String],
+) -> Option<Vec<String>> {
+    let mut absolute_path = module_path.to_vec();
+    let mut remaining = segments;
+    match segments.first().map(String::as_str) {
+        Some("crate") => {
+            absolute_path.truncate(1);
+            remaining = &segments[1..];
+        }
+        Some("self") => remaining = &segments[1..],
+        _ => {}
+    }
+    while remaining.first().map(String::as_str) == Some("super") {
+        // can not go beyond the crate root
+        if absolute_path.len() <= 1 {
+            return None;
+        }
+        absolute_path.pop();
+        remaining = &remaining[1..];
+    }
+    absolute_path.extend(remaining.iter().cloned());
+    Some(absolute_path)
+}
+
+// `use` paths are relative to the crate (2015 style), the module itself, or
+// refer to an external crate.
+fn absolute_use_path(
+    module_path: &[String],
+    use_path: &[String],
+    module_paths: &HashSet<String>,
+    local_items: &[TreeNode],
+) -> Vec<String> {
+    let Some(first) = use_path.first() else {
+        return Vec::new();
+    };
+    match first.as_str() {
+        "crate" | "self" | "super" => {
+            relative_path(module_path, use_path).unwrap_or_default()
+        }
+        _ => {
+            let mut local_path = module_path.to_vec();
+            local_path.push(first.clone());
+            let is_local = module_paths.contains(&local_path.join("::"))
+                || local_items.iter().any(|item| item.name() == first);
+            if is_local {
+                local_path.extend(use_path[1..].iter().cloned());
+                local_path
+            } else {
+                use_path.to_vec()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::localfs::FilePath;
+    use crate::modules::UseDeclaration;
+    use crate::types::{RustStruct, RustType, StructKind, Visibility};
+
+    fn path(path: &str) -> Vec<String> {
+        path.split("::").map(ToString::to_string).collect()
+    }
+
+    fn import(alias: Option<&str>, use_path: &str) -> UseDeclaration {
+        UseDeclaration {
+            alias: alias.map(ToString::to_string),
+            path: path(use_path),
+        }
+    }
+
+    fn root(
+        module_path: &str,
+        items: &[&str],
+        use_declarations: Vec<UseDeclaration>,
+    ) -> RootNode {
+        let file = format!("{}.rs", module_path.replace("::", "/"));
+        let mut root = RootNode::new(
+            FilePath::new(Path::new("src"), Path::new(&file)),
+            path(module_path),
+            Vec::new(),
+            use_declarations,
+        );
+        for name in items {
+            let rust_struct = RustStruct::new(
+                &format!("struct:{}::{}", module_path, name),
+                Visibility::Public,
+                name,
+                StructKind::Unit,
+            );
+            root.add_child(TreeNode::new(RustType::Struct(rust_struct)));
+        }
+        root
+    }
+
+    fn item(path: &str) -> Resolution {
+        Resolution::Item(path.to_string())
+    }
+
+    #[test]
+    fn resolves_local_items_and_associated_paths() {
+        let resolver = NameResolver::new(&[root(
+            "crate::config",
+            &["Config"],
+            Vec::new(),
+        )]);
+        let module = path("crate::config");
+        assert_eq!(
+            resolver.resolve(&module, "Config"),
+            item("crate::config::Config")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "Config::new"),
+            item("crate::config::Config")
+        );
+        assert_eq!(resolver.resolve(&module, "Missing"), Resolution::Unknown);
+    }
+
+    #[test]
+    fn resolves_imports_renames_and_globs() {
+        let resolver = NameResolver::new(&[
+            root("crate::config", &["Config", "Limits"], Vec::new()),
+            root(
+                "crate::map",
+                &[],
+                vec![
+                    import(Some("Config"), "crate::config::Config"),
+                    import(Some("Settings"), "crate::config::Config"),
+                    import(None, "super::config"),
+                ],
+            ),
+        ]);
+        let module = path("crate::map");
+        assert_eq!(
+            resolver.resolve(&module, "Config"),
+            item("crate::config::Config")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "Settings"),
+            item("crate::config::Config")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "Limits"),
+            item("crate::config::Limits")
+        );
+    }
+
+    #[test]
+    fn resolves_relative_prefixes() {
+        let resolver = NameResolver::new(&[
+            root("crate", &["Root"], Vec::new()),
+            root("crate::tree", &["TreeNode"], Vec::new()),
+            root("crate::tree::builder", &[], Vec::new()),
+        ]);
+        let module = path("crate::tree::builder");
+        assert_eq!(
+            resolver.resolve(&module, "super::TreeNode"),
+            item("crate::tree::TreeNode")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "crate::Root"),
+            item("crate::Root")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "super::super::super::Root"),
+            Resolution::Unknown
+        );
+        assert_eq!(resolver.resolve(&module, "Self::new"), Resolution::Unknown);
+    }
+
+    #[test]
+    fn external_paths_are_not_resolved() {
+        let resolver = NameResolver::new(&[root(
+            "crate::map",
+            &[],
+            vec![import(Some("HashMap"), "std::collections::HashMap")],
+        )]);
+        let module = path("crate::map");
+        assert_eq!(
+            resolver.resolve(&module, "std::fmt::Display"),
+            Resolution::External
+        );
+        assert_eq!(
+            resolver.resolve(&module, "::serde::Serialize"),
+            Resolution::External
+        );
+        assert_eq!(resolver.resolve(&module, "HashMap"), Resolution::External);
+    }
+
+    #[test]
+    fn glob_import_cycles_end() {
+        let resolver = NameResolver::new(&[
+            root("crate::a", &[], vec![import(None, "crate::b")]),
+            root("crate::b", &[], vec![import(None, "crate::a")]),
+        ]);
+        assert_eq!(
+            resolver.resolve(&path("crate::a"), "Missing"),
+            Resolution::Unknown
+        );
+    }
+
+    #[test]
+    fn binaries_refer_to_their_library() {
+        let resolver = NameResolver::new(&[
+            root("demo", &["run"], Vec::new()),
+            root("demo::util", &["helper"], Vec::new()),
+            root("tool@bin", &["run"], Vec::new()),
+            root("tool@bin::cmd", &[], Vec::new()),
+        ]);
+        let module = path("tool@bin::cmd");
+        assert_eq!(
+            resolver.resolve(&module, "demo::util::helper"),
+            item("demo::util::helper")
+        );
+        assert_eq!(
+            resolver.resolve(&module, "crate::run"),
+            item("tool@bin::run")
+        );
+        assert_eq!(resolver.resolve(&path("demo"), "run"), item("demo::run"));
+    }
+
+    #[test]
+    fn absolute_paths_from_the_module() {
+        let module = path("crate::tree::builder");
+        assert_eq!(
+            absolute_path(&module, "super::TreeNode"),
+            "crate::tree::TreeNode"
+        );
+        assert_eq!(
+            absolute_path(&module, "self::Item"),
+            "crate::tree::builder::Item"
+        );
+        assert_eq!(
+            absolute_path(&module, "crate::map::list_map"),
+            "crate::map::list_map"
+        );
+        assert_eq!(
+            absolute_path(&module, "Item"),
+            "crate::tree::builder::Item"
+        );
+    }
+}
@@ -1,20 +1,36 @@
+use std::collections::HashSet;
+
 use super::dependencies::Dependencies;
-use super::{Dependents, TreeNode};
+use super::{Dependency, Dependent, Dependents, Order, TreeNode};
 use crate::localfs::FilePath;
-use crate::print_config::PrintConfigBuilder;
+use crate::modules::UseDeclaration;
+use crate::print_config::{PrintConfig, PrintConfigBuilder};
+use crate::query::Query;
+use crate::types::CodeStyle;
 use crate::writers::ClippyWriter;
 
 pub struct RootNode {
     file_path: FilePath,
+    module_path: Vec<String>,
+    inline_path: Vec<String>,
+    use_declarations: Vec<UseDeclaration>,
     dependencies: Dependencies,
     dependents: Dependents,
     children: Vec<TreeNode>,
 }
 
 impl RootNode {
-    pub fn new(file_path: FilePath) -> Self {
+    pub fn new(
+        file_path: FilePath,
+        module_path: Vec<String>,
+        inline_path: Vec<String>,
+        use_declarations: Vec<UseDeclaration>,
+    ) -> Self {
         RootNode {
             file_path,
+            module_path,
+            inline_path,
+            use_declarations,
             dependencies: Dependencies::default(),
             dependents: Dependents::default(),
             children: Vec::new(),
@@ -25,10 +41,54 @@ impl RootNode {
         &self.file_path
     }
 
+    /// Full module path of the items in this node, starting with the name
+    /// of the crate, e.g. `["codeclippy", "tree", "builder"]`.
+    pub fn module_path(&self) -> &Vec<String> {
+        &self.module_path
+    }
+
+    /// Module path as written within the crate, e.g.
+    /// `["crate", "tree", "builder"]`.
+    pub fn crate_module_path(&self) -> Vec<String> {
+        let mut module_path = self.module_path.clone();
+        if let Some(first) = module_path.first_mut() {
+            *first = "crate".to_string();
+        }
+        module_path
+    }
+
+    /// Inline modules (`mod name { .. }`) leading to this node within its
+    /// file. Empty for the file itself.
+    pub fn inline_path(&self) -> &Vec<String> {
+        &self.inline_path
+    }
+
+    pub fn use_declarations(&self) -> &Vec<UseDeclaration> {
+        &self.use_declarations
+    }
+
+    /// Module path of the file this node is part of.
+    pub fn file_module_path(&self) -> Vec<String> {
+        let file_depth = self.module_path.len() - self.inline_path.len();
+        self.module_path[..file_depth].to_vec()
+    }
+
+    /// Path of this node as used in a `PrintConfig`: the relative file
+    /// path, followed by any inline modules.
+    pub fn path(&self) -> Vec<String> {
+        let mut path = vec![self.file_path.relative_path()];
+        path.extend(self.inline_path.iter().cloned());
+        path
+    }
+
     pub fn dependencies(&self) -> &Dependencies {
         &self.dependencies
     }
 
+    pub fn dependencies_mut(&mut self) -> &mut Dependencies {
+        &mut self.dependencies
+    }
+
     pub fn set_dependencies(&mut self, dependencies: Dependencies) {
         self.dependencies = dependencies;
     }
@@ -37,8 +97,36 @@ impl RootNode {
         &self.dependents
     }
 
-    pub fn set_dependents(&mut self, dependents: Dependents) {
-        self.dependents = dependents;
+    pub fn dependents_mut(&mut self) -> &mut Dependents {
+        &mut self.dependents
+    }
+
+    /// Dependencies of all `root_nodes`, each with the root node it is
+    /// listed for. A dependency shared by items in several files is only
+    /// listed for the first of them.
+    pub fn unique_dependencies(
+        root_nodes: &[RootNode],
+    ) -> impl Iterator<Item = (&RootNode, &Dependency)> {
+        let mut listed = HashSet::new();
+        root_nodes
+            .iter()
+            .flat_map(|root| {
+                root.dependencies().iter().map(move |item| (root, item))
+            })
+            .filter(move |(_, item)| listed.insert(item.node().id()))
+    }
+
+    /// Dependents of all `root_nodes`, like `unique_dependencies`.
+    pub fn unique_dependents(
+        root_nodes: &[RootNode],
+    ) -> impl Iterator<Item = (&RootNode, &Dependent)> {
+        let mut listed = HashSet::new();
+        root_nodes
+            .iter()
+            .flat_map(|root| {
+                root.dependents().iter().map(move |item| (root, item))
+            })
+            .filter(move |(_, item)| listed.insert(item.node().id()))
     }
 
     pub fn children(&self) -> &Vec<TreeNode> {
@@ -52,24 +140,53 @@ impl RootNode {
     pub fn print(
         &self,
         writer: &mut Box<dyn ClippyWriter>,
-        filter: Option<&str>,
+        filter: Option<&Query>,
         use_full_path: bool,
+        show_ids: bool,
+        code_style: CodeStyle,
     ) {
         for child in self.children() {
-            let config = PrintConfigBuilder::new()
-                .filter(filter.map(|s| s.to_string()))
-                .path(vec![
-                    self.file_path.relative_path().to_string(),
-                    child.name().to_string(),
-                ])
-                .is_linked(false)
-                .use_full_path(use_full_path)
-                .build();
-
+            let mut config =
+                self.child_print_config(child, filter, use_full_path);
+            config.set_show_ids(show_ids);
+            config.set_code_style(code_style);
+            config.set_source(&self.file_path.relative_path());
             child.print(writer, config);
         }
     }
 
+    /// Items matching the filter, as printed by `print`.
+    pub fn matching_nodes(
+        &self,
+        filter: Option<&Query>,
+        use_full_path: bool,
+    ) -> Vec<&TreeNode> {
+        let mut nodes = Vec::new();
+        for child in self.children() {
+            let config = self.child_print_config(child, filter, use_full_path);
+            child.collect_matching(&config, &mut nodes);
+        }
+        nodes
+    }
+
+    pub fn child_print_config(
+        &self,
+        child: &TreeNode,
+        filter: Option<&Query>,
+        use_full_path: bool,
+    ) -> PrintConfig {
+        let mut path = self.path();
+        path.push(child.name().to_string());
+
+        PrintConfigBuilder::new()
+            .filter(filter.cloned())
+            .path(path)
+            .module_path(self.file_module_path())
+            .is_linked(false)
+            .use_full_path(use_full_path)
+            .build()
+    }
+
     pub fn find_child_by_name(&self, name: &str) -> Option<&TreeNode> {
         self.children.iter().find(|&child| child.name() == name)
     }
@@ -78,6 +195,14 @@ impl RootNode {
         self.find_child_by_name(name).is_some()
     }
 
+    /// Sort the items of the file in `order`, instead of by kind.
+    pub fn sort_children(&mut self, order: Order) {
+        let source = self.file_path.relative_path();
+        self.children.sort_by(|a, b| {
+            order.compare((a, Some(&source)), (b, Some(&source)))
+        });
+    }
+
     pub fn add_child(&mut self, child: TreeNode) {
         self.children.push(child);
     }
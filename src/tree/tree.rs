@@ -1,5 +1,6 @@
+use crate::helpers::source_location;
 use crate::print_config::{PrintConfig, PrintConfigBuilder};
-use crate::types::{Identifiable, RustType};
+use crate::types::{CodeStyle, Identifiable, RustType};
 use crate::writers::ClippyWriter;
 
 #[derive(Debug, Clone)]
@@ -9,6 +10,8 @@ pub struct TreeNode {
     rtype: RustType,
     children: Option<Vec<TreeNode>>,
     pub link: Option<Box<TreeNode>>,
+    // printed as a skeleton whatever the style, to fit a token budget
+    skeleton: bool,
 }
 
 impl TreeNode {
@@ -19,6 +22,7 @@ impl TreeNode {
             rtype,
             children: None,
             link: None,
+            skeleton: false,
         }
     }
 
@@ -38,6 +42,29 @@ impl TreeNode {
         &mut self.rtype
     }
 
+    pub fn set_skeleton(&mut self) {
+        self.skeleton = true;
+    }
+
+    /// `style` as it applies to this node, which may have been reduced to
+    /// a skeleton.
+    pub fn code_style(&self, style: CodeStyle) -> CodeStyle {
+        if self.skeleton {
+            style.as_skeleton()
+        } else {
+            style
+        }
+    }
+
+    /// The item in `style`, or as a skeleton if reduced to one.
+    pub fn print_code(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        style: CodeStyle,
+    ) {
+        self.rtype.print_code(writer, self.code_style(style));
+    }
+
     pub fn children(&self) -> &Option<Vec<TreeNode>> {
         &self.children
     }
@@ -63,10 +90,56 @@ impl TreeNode {
             return self.print_linked_node(writer, linked_node, &config);
         }
 
-        self.rtype.print(writer);
+        self.print_with_header(
+            writer,
+            config.source(),
+            config.show_ids(),
+            config.code_style(),
+        );
         true // any of the print_ functions will print something
     }
 
+    /// The item preceded by its `@file:line:` header if `source` is given,
+    /// and its id if `show_ids` is set.
+    pub fn print_with_header(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        source: Option<&str>,
+        show_ids: bool,
+        code_style: CodeStyle,
+    ) {
+        if let Some(source) = source {
+            writer.start_group(source);
+        }
+        writer.start_item(&self.id);
+        if let Some(source) = source {
+            let location = source_location(source, self.rtype.span());
+            let _ = writeln!(writer, "@{}:", location);
+        }
+        if show_ids {
+            let _ = writeln!(writer, "// id: {}", self.id);
+        }
+        self.print_code(writer, code_style);
+    }
+
+    /// Nodes that `print` would print, for output formats that are not
+    /// written item by item.
+    pub fn collect_matching<'a>(
+        &'a self,
+        config: &PrintConfig,
+        nodes: &mut Vec<&'a TreeNode>,
+    ) {
+        if self.should_print(config) {
+            nodes.push(self.link.as_deref().unwrap_or(self));
+            return;
+        }
+        for child in self.children.iter().flatten() {
+            let mut child_config = config.clone();
+            child_config.add_to_path(child.name.clone());
+            child.collect_matching(&child_config, nodes);
+        }
+    }
+
     fn print_children(
         &self,
         writer: &mut Box<dyn ClippyWriter>,
@@ -89,7 +162,7 @@ impl TreeNode {
         let current_path = config.path().join("::");
 
         let filter_path = if config.use_full_path() {
-            current_path
+            current_path.clone()
         } else {
             // remove first element (filename) in case search is not
             // scoped to single file
@@ -99,11 +172,32 @@ impl TreeNode {
                 .collect::<Vec<&str>>()
                 .join("::")
         };
-        config.filter().as_deref().map_or(true, |f| {
-            filter_path == f
-                || (filter_path.starts_with(f)
-                    && filter_path[f.len()..].starts_with("::"))
-                || self.name() == f
+        // e.g. codeclippy::tree::builder::TreeBuilder, which may also be
+        // written as crate::tree::builder::TreeBuilder
+        let module_item_path = config
+            .module_path()
+            .iter()
+            .chain(config.path().iter().skip(1))
+            .cloned()
+            .collect::<Vec<String>>();
+        let crate_item_path = std::iter::once("crate")
+            .chain(module_item_path.iter().skip(1).map(String::as_str))
+            .collect::<Vec<&str>>()
+            .join("::");
+        let module_item_path = module_item_path.join("::");
+
+        config.filter().as_ref().is_none_or(|query| {
+            query.matches_attributes(self.rtype.attributes())
+                // an id refers to exactly one item
+                && (query.matches(self.id())
+                    || query.matches_path(&filter_path, self.name())
+                    // patterns may also target the file part of the path
+                    || (!query.is_exact()
+                        && query.matches_path(&current_path, self.name()))
+                    || (!config.module_path().is_empty()
+                        && (query.matches_path(&module_item_path, self.name())
+                            || query
+                                .matches_path(&crate_item_path, self.name()))))
         })
     }
 
@@ -124,6 +218,8 @@ impl TreeNode {
             .path(vec![linked_node.name.clone()])
             .is_linked(true)
             .use_full_path(config.use_full_path())
+            .show_ids(config.show_ids())
+            .code_style(config.code_style())
             .build();
 
         linked_node.print(writer, linked_config)
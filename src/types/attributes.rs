@@ -0,0 +1,44 @@
+use super::CodeStyle;
+
+/// Doc comment and other outer attributes of an item, as written.
+#[derive(Debug, Clone, Default)]
+pub struct Attributes {
+    // lines of the doc comment, without the leading `///`
+    docs: Vec<String>,
+    // e.g. `#[derive(Debug, Clone)]`
+    attrs: Vec<String>,
+}
+
+impl Attributes {
+    pub fn new(docs: Vec<String>, attrs: Vec<String>) -> Self {
+        Attributes { docs, attrs }
+    }
+
+    pub fn attrs(&self) -> &[String] {
+        &self.attrs
+    }
+
+    /// The doc comment as a single string, one line per doc line.
+    pub fn doc_str(&self) -> String {
+        self.docs
+            .iter()
+            .map(|line| line.strip_prefix(' ').unwrap_or(line))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Lines preceding the item, as far as selected in `style`: the doc
+    /// comment followed by the other attributes.
+    pub fn code_lines(&self, style: CodeStyle) -> Vec<String> {
+        let mut lines = Vec::new();
+        if style.docs {
+            lines.extend(self.docs.iter().map(|line| format!("///{}", line)));
+        }
+        if style.attrs {
+            lines.extend(self.attrs.iter().flat_map(|attr| {
+                attr.lines().map(ToString::to_string).collect::<Vec<_>>()
+            }));
+        }
+        lines
+    }
+}
@@ -1,5 +1,38 @@
+use std::fmt::Write;
 use std::sync::{Arc, Mutex};
 
+use super::{Attributes, Span};
+use crate::localfs::FilePath;
+
+/// How items are written by `RustType::print_code`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CodeStyle {
+    /// Prefix each line with its line number in the source file.
+    pub line_numbers: bool,
+    /// Copy items from their source file instead of reconstructing them,
+    /// which keeps attributes, doc comments and formatting.
+    pub verbatim: bool,
+    /// Precede reconstructed items by their doc comments.
+    pub docs: bool,
+    /// Precede reconstructed items by their other attributes.
+    pub attrs: bool,
+    /// Leave out function bodies and macro rules, so only the shape of the
+    /// items remains.
+    pub skeleton: bool,
+}
+
+impl CodeStyle {
+    /// The style without function bodies, which are only available
+    /// reconstructed.
+    pub fn as_skeleton(self) -> Self {
+        CodeStyle {
+            skeleton: true,
+            verbatim: false,
+            ..self
+        }
+    }
+}
+
 pub fn pretty_code_fmt(content: &mut String) {
     let err_msg_arc = Arc::new(Mutex::new(String::new()));
 
@@ -14,6 +47,108 @@ pub fn pretty_code_fmt(content: &mut String) {
     handle_pretty_print_result(content, pretty_content, err_msg_arc);
 }
 
+/// Prefix each line of `code` with its line number, counting from
+/// `first_line`. Without a first line the gutter is left empty, which keeps
+/// code that is not in the source file (e.g. an impl header) aligned.
+fn number_lines(code: &str, first_line: Option<usize>) -> String {
+    let mut numbered = String::new();
+    for (offset, line) in code.lines().enumerate() {
+        let number = first_line
+            .map(|first_line| (first_line + offset).to_string())
+            .unwrap_or_default();
+        let numbered_line = format!("{:>5} | {}", number, line);
+        let _ = writeln!(numbered, "{}", numbered_line.trim_end());
+    }
+    numbered
+}
+
+/// `code` as lines ending in a newline, numbered from `first_line` if
+/// `line_numbers` is set.
+pub fn code_lines(
+    code: &str,
+    first_line: Option<usize>,
+    line_numbers: bool,
+) -> String {
+    if line_numbers {
+        return number_lines(code, first_line);
+    }
+    code.lines().map(|line| format!("{}\n", line)).collect()
+}
+
+/// Reconstructed `code` as lines ending in a newline. Only its first line
+/// is numbered if `line_numbers` is set, as the item starts there, but its
+/// other lines need not match the source file.
+fn reconstructed_lines(
+    code: &str,
+    first_line: Option<usize>,
+    line_numbers: bool,
+) -> String {
+    if !line_numbers {
+        return code_lines(code, None, false);
+    }
+    let (first, rest) = code.split_once('\n').unwrap_or((code, ""));
+    format!(
+        "{}{}",
+        number_lines(first, first_line),
+        number_lines(rest, None)
+    )
+}
+
+/// An item in the given style: the text of `span` in `file_path` if
+/// verbatim and readable, `reconstructed` (preceded by `attributes` as
+/// selected in the style) otherwise.
+pub fn item_code(
+    reconstructed: impl FnOnce() -> String,
+    attributes: &Attributes,
+    span: Option<Span>,
+    file_path: Option<&FilePath>,
+    style: CodeStyle,
+) -> String {
+    if style.verbatim {
+        if let (Some(span), Some(file_path)) = (span, file_path) {
+            match span.source_text(file_path) {
+                Ok(text) => {
+                    let first_line = Some(span.outer_start.line);
+                    return code_lines(&text, first_line, style.line_numbers);
+                }
+                Err(err) => log::warn!(
+                    "Failed to read {}, reconstructing the item: {}",
+                    file_path.relative_path(),
+                    err
+                ),
+            }
+        }
+    }
+    let code = reconstructed();
+    let attribute_lines = attributes.code_lines(style);
+    if attribute_lines.is_empty() {
+        let first_line = span.map(|span| span.start.line);
+        return reconstructed_lines(&code, first_line, style.line_numbers);
+    }
+    // attributes are indented like the item, e.g. a method
+    let indent: String =
+        code.chars().take_while(|c| c.is_whitespace()).collect();
+    let attribute_text = attribute_lines
+        .iter()
+        .map(|line| format!("{}{}", indent, line))
+        .collect::<Vec<_>>()
+        .join("\n");
+    // the item keeps its own line numbers if only some attributes are shown
+    format!(
+        "{}{}",
+        reconstructed_lines(
+            &attribute_text,
+            span.map(|span| span.outer_start.line),
+            style.line_numbers
+        ),
+        reconstructed_lines(
+            &code,
+            span.map(|span| span.start.line),
+            style.line_numbers
+        )
+    )
+}
+
 fn set_custom_panic_hook(err_msg_arc: Arc<Mutex<String>>) {
     let old_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |panic_info| {
@@ -1,16 +1,29 @@
+mod attributes;
 mod format;
+mod rust_const;
 mod rust_enum;
 mod rust_function;
 mod rust_impl;
+mod rust_macro;
+mod rust_static;
 mod rust_struct;
 mod rust_trait;
 mod rust_type;
+mod rust_type_alias;
+mod span;
 mod visibility;
 
+pub use attributes::Attributes;
+pub use format::CodeStyle;
+pub use rust_const::RustConst;
 pub use rust_enum::RustEnum;
 pub use rust_function::RustFunction;
 pub use rust_impl::RustImpl;
-pub use rust_struct::RustStruct;
+pub use rust_macro::RustMacro;
+pub use rust_static::RustStatic;
+pub use rust_struct::{Field, RustStruct, StructKind};
 pub use rust_trait::RustTrait;
 pub use rust_type::{Identifiable, RustType};
+pub use rust_type_alias::RustTypeAlias;
+pub use span::Span;
 pub use visibility::Visibility;
@@ -1,18 +1,31 @@
 use std::fmt;
 use std::fmt::{Display, Write};
 
-use super::format::pretty_code_fmt;
-use super::{Identifiable, RustFunction, Visibility};
-use crate::helpers::generate_id;
+use super::format::{item_code, pretty_code_fmt, CodeStyle};
+use super::rust_function::impl_blocks_code;
+use super::{
+    Attributes, Field, Identifiable, RustFunction, Span, StructKind, Visibility,
+};
+use crate::helpers::{generate_id, qualify_id};
+use crate::localfs::FilePath;
 use crate::writers::ClippyWriter;
 
+/// Name, kind and fields of a variant, `Named` and `Tuple` variants are
+/// written like the structs of that kind.
+pub type Variant = (String, StructKind, Vec<Field>);
+
 #[derive(Debug, Clone)]
 pub struct RustEnum {
     id: String,
     visibility: Visibility,
     name: String,
-    variants: Vec<(String, Vec<String>)>,
+    generics: String,
+    where_clause: String,
+    variants: Vec<Variant>,
     methods: Option<Vec<RustFunction>>,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
 }
 
 impl Identifiable for RustEnum {
@@ -37,17 +50,62 @@ impl RustEnum {
     pub fn new_with_data(
         name: String,
         visibility: Visibility,
-        variants: Vec<(String, Vec<String>)>,
+        variants: Vec<Variant>,
     ) -> Self {
         RustEnum {
-            id: generate_id(&name),
+            id: generate_id("enum", &name),
             name,
             visibility,
+            generics: String::new(),
+            where_clause: String::new(),
             variants,
             methods: None,
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Prefix the id, and those of its methods, with the module the item
+    /// is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+        for method in self.methods.iter_mut().flatten() {
+            method.qualify_id(scope);
         }
     }
 
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    /// Generic parameters (e.g. `<T: Clone>`) and where clause, as written.
+    pub fn set_generics(&mut self, generics: String, where_clause: String) {
+        self.generics = generics;
+        self.where_clause = where_clause;
+    }
+
     pub fn add_methods(&mut self, methods: Vec<RustFunction>) {
         match &mut self.methods {
             Some(existing_methods) => existing_methods.extend(methods),
@@ -66,28 +124,68 @@ impl RustEnum {
         } else {
             format!("{} ", self.visibility)
         };
-        write!(&mut enum_str, "{}enum {} {{\n", visibility, self.name).unwrap();
-        for (variant, fields) in &self.variants {
-            let fields_str = if fields.is_empty() {
-                String::from("")
-            } else {
-                format!("({})", fields.join(", "))
+        writeln!(
+            &mut enum_str,
+            "{}enum {}{} {} {{",
+            visibility, self.name, self.generics, self.where_clause
+        )
+        .unwrap();
+        for (variant, kind, fields) in &self.variants {
+            let fields_str = match kind {
+                StructKind::Named | StructKind::Union => {
+                    let fields: Vec<String> = fields
+                        .iter()
+                        .map(|(_, name, field_type)| {
+                            format!("{}: {}", name, field_type)
+                        })
+                        .collect();
+                    format!(" {{ {} }}", fields.join(", "))
+                }
+                StructKind::Tuple => {
+                    let field_types: Vec<&str> = fields
+                        .iter()
+                        .map(|(_, _, field_type)| field_type.as_str())
+                        .collect();
+                    format!("({})", field_types.join(", "))
+                }
+                StructKind::Unit => String::new(),
             };
-            write!(&mut enum_str, "    {}{},\n", variant, fields_str).unwrap();
+            writeln!(&mut enum_str, "    {}{},", variant, fields_str).unwrap();
         }
-        write!(&mut enum_str, "}}\n").unwrap();
+        writeln!(&mut enum_str, "}}").unwrap();
         pretty_code_fmt(&mut enum_str);
         enum_str
     }
 
+    /// `enum_base_block_str` in the given style.
+    pub fn definition_code(&self, style: CodeStyle) -> String {
+        let reconstructed = || self.enum_base_block_str();
+        item_code(
+            reconstructed,
+            &self.attributes,
+            self.span,
+            self.file_path(),
+            style,
+        )
+    }
+
+    /// The definition followed by the methods in the impl blocks they are
+    /// defined in, all in the given style.
+    pub fn code(&self, style: CodeStyle) -> String {
+        let mut code = self.definition_code(style);
+        let methods = self.methods.as_deref().unwrap_or_default();
+        code.push_str(&impl_blocks_code(methods, style));
+        code
+    }
+
     pub fn enum_impl_block_str(&self) -> String {
         let mut methods_str = String::new();
         if let Some(methods) = &self.methods {
-            write!(&mut methods_str, "impl {} {{\n", self.name).unwrap();
+            writeln!(&mut methods_str, "impl {} {{", self.name).unwrap();
             for method in methods {
-                write!(&mut methods_str, "{}\n", method).unwrap();
+                writeln!(&mut methods_str, "{}", method).unwrap();
             }
-            write!(&mut methods_str, "}}\n").unwrap();
+            writeln!(&mut methods_str, "}}").unwrap();
         }
         pretty_code_fmt(&mut methods_str);
         methods_str
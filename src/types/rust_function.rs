@@ -7,10 +7,10 @@ use proc_macro2::LineColumn;
 use quote::quote;
 use syn::visit::Visit;
 
-use super::format::pretty_code_fmt;
-use super::{Identifiable, Visibility};
+use super::format::{code_lines, item_code, pretty_code_fmt, CodeStyle};
+use super::{Attributes, Identifiable, Span, Visibility};
 use crate::function_visitor::FunctionCallVisitor;
-use crate::helpers::generate_id;
+use crate::helpers::{generate_id, qualify_id};
 use crate::localfs::FilePath;
 use crate::writers::ClippyWriter;
 
@@ -25,12 +25,23 @@ pub struct RustFunction {
     block: Option<Box<syn::Block>>,
     methods: Option<Vec<RustFunction>>,
     instantiated_items: HashSet<String>,
+    // implementing type for methods, used to resolve `self` and `Self`
+    self_type: Option<String>,
+    generics: String,
+    where_clause: String,
+    // e.g. `impl<T> Default for Foo<T>` for methods, printed around the
+    // method when it is listed on its own
+    impl_header: Option<String>,
+    // the whole impl block, copied instead of the method if verbatim
+    impl_span: Option<Span>,
+    span: Option<Span>,
+    attributes: Attributes,
 }
 
 impl RustFunction {
     pub fn new(visibility: Visibility, name: &str) -> Self {
         Self {
-            id: generate_id(name),
+            id: generate_id("fn", name),
             name: name.to_string(),
             visibility,
             inputs: Vec::new(),
@@ -39,6 +50,13 @@ impl RustFunction {
             block: None,
             methods: None,
             instantiated_items: HashSet::new(),
+            self_type: None,
+            generics: String::new(),
+            where_clause: String::new(),
+            impl_header: None,
+            impl_span: None,
+            span: None,
+            attributes: Attributes::default(),
         }
     }
 
@@ -51,7 +69,7 @@ impl RustFunction {
         block: Option<Box<syn::Block>>,
     ) -> Self {
         Self {
-            id: generate_id(name),
+            id: generate_id("fn", name),
             name: name.to_string(),
             visibility,
             inputs,
@@ -60,6 +78,13 @@ impl RustFunction {
             block,
             methods: None,
             instantiated_items: HashSet::new(),
+            self_type: None,
+            generics: String::new(),
+            where_clause: String::new(),
+            impl_header: None,
+            impl_span: None,
+            span: None,
+            attributes: Attributes::default(),
         }
     }
 
@@ -67,6 +92,12 @@ impl RustFunction {
         &self.id
     }
 
+    /// Prefix the id with the module, or the impl target for methods,
+    /// the function is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -79,19 +110,58 @@ impl RustFunction {
         &self.instantiated_items
     }
 
+    pub fn set_self_type(&mut self, self_type: &str) {
+        self.self_type = Some(self_type.to_string());
+    }
+
+    /// Generic parameters (e.g. `<T: Clone>`) and where clause, as written.
+    pub fn set_generics(&mut self, generics: String, where_clause: String) {
+        self.generics = generics;
+        self.where_clause = where_clause;
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    /// Header and span of the impl (or trait) block the method is defined
+    /// in.
+    pub fn set_impl_block(&mut self, impl_header: String, impl_span: Span) {
+        self.impl_header = Some(impl_header);
+        self.impl_span = Some(impl_span);
+    }
+
     pub fn signature_str(&self) -> String {
         let mut signature = String::new();
 
         // Write function signature
         write!(
             &mut signature,
-            "{}fn {}(",
+            "{}fn {}{}(",
             if self.visibility().to_string().is_empty() {
                 String::from("")
             } else {
                 format!("{} ", self.visibility)
             },
-            self.name
+            self.name,
+            self.generics
         )
         .unwrap();
 
@@ -113,14 +183,23 @@ impl RustFunction {
         if let Some(output) = &self.output {
             write!(&mut signature, "-> {}", output).unwrap();
         }
+        if !self.where_clause.is_empty() {
+            write!(&mut signature, " {}", self.where_clause).unwrap();
+        }
 
         // Temporarily add an empty body and format, so we can parse the
         // signature through pretty_code_fmt separately
         signature.push_str(" {}\n");
         pretty_code_fmt(&mut signature);
 
-        // Remove the temporary (emtpy) body to get clean signature
-        signature.trim_end_matches(" {}\n").to_string()
+        // Remove the temporary (emtpy) body to get clean signature, a
+        // where clause puts the body on a line of its own
+        let signature = signature.trim_end().trim_end_matches("{}");
+        if self.where_clause.is_empty() {
+            signature.trim_end().to_string()
+        } else {
+            signature.to_string()
+        }
     }
 
     fn body_str(&self) -> String {
@@ -146,6 +225,14 @@ impl RustFunction {
         }
         body
     }
+    /// The signature followed by `;`, e.g. `pub fn new() -> Self;`.
+    pub fn skeleton_str(&self) -> String {
+        // the last predicate of a where clause ends in a comma
+        let signature = self.signature_str();
+        let signature = signature.trim_end().trim_end_matches(',');
+        format!("{};\n", signature)
+    }
+
     pub fn function_block_str(&self) -> String {
         let mut full_function = String::new();
 
@@ -154,6 +241,42 @@ impl RustFunction {
         full_function.push_str(&self.body_str());
         full_function
     }
+
+    /// The function wrapped in the header of its impl block, so a method
+    /// printed on its own shows which type it belongs to.
+    pub fn impl_block_code(&self, style: CodeStyle) -> String {
+        if self.impl_header.is_none() {
+            return self.method_code(style);
+        }
+        // the other methods of the block are left out, so it is not copied
+        wrap_in_impl_block(&[self], false, style)
+    }
+
+    // the method as part of its impl block, the source text keeps its
+    // indentation
+    fn method_code(&self, style: CodeStyle) -> String {
+        let reconstructed = || match (&self.impl_header, style.skeleton) {
+            (Some(_), true) => indent(&self.skeleton_str()),
+            (Some(_), false) => self.indented_block_str(),
+            (None, true) => self.skeleton_str(),
+            (None, false) => self.function_block_str(),
+        };
+        item_code(
+            reconstructed,
+            &self.attributes,
+            self.span,
+            self.file_path(),
+            style,
+        )
+    }
+
+    // the function as part of an impl block: the body keeps the
+    // indentation of the source file, only the (formatted) signature needs
+    // to be indented
+    fn indented_block_str(&self) -> String {
+        let signature = indent(&self.signature_str());
+        format!("{}{}", signature.trim_end(), self.body_str().trim_end())
+    }
 }
 
 impl Identifiable for RustFunction {
@@ -202,7 +325,10 @@ impl Display for RustFunction {
 impl RustFunction {
     pub fn extract_function_body(&mut self) {
         if let Some(ref block) = self.block {
-            let mut body_visitor = FunctionCallVisitor::default();
+            let mut body_visitor = FunctionCallVisitor::new(
+                self.self_type.as_deref(),
+                &self.inputs,
+            );
             body_visitor.visit_block(block);
             match &mut self.methods {
                 Some(methods) => methods.extend(body_visitor.functions),
@@ -214,6 +340,82 @@ impl RustFunction {
     }
 }
 
+/// Methods in the given style, each in the impl block it is defined in.
+/// Consecutive methods of the same impl block share it, which is copied as
+/// a whole if verbatim.
+pub fn impl_blocks_code(methods: &[RustFunction], style: CodeStyle) -> String {
+    let mut blocks = String::new();
+    let mut methods = methods.iter().peekable();
+    while let Some(method) = methods.next() {
+        if method.impl_header.is_none() {
+            blocks.push_str(&method.method_code(style));
+            continue;
+        }
+        let mut block_methods = vec![method];
+        while let Some(next) = methods.next_if(|next| {
+            next.impl_header == method.impl_header
+                && next.impl_span == method.impl_span
+        }) {
+            block_methods.push(next);
+        }
+        blocks.push_str(&wrap_in_impl_block(&block_methods, true, style));
+    }
+    blocks
+}
+
+// methods of one impl block, with the block around them. The block is
+// copied from the source if verbatim and `copy_block` is set, which
+// requires `methods` to be all of its methods.
+fn wrap_in_impl_block(
+    methods: &[&RustFunction],
+    copy_block: bool,
+    style: CodeStyle,
+) -> String {
+    let first = methods[0];
+    let span = first.impl_span;
+    if style.verbatim && copy_block {
+        if let (Some(span), Some(file_path)) = (span, first.file_path()) {
+            match span.source_text(file_path) {
+                Ok(text) => {
+                    let first_line = Some(span.outer_start.line);
+                    return code_lines(&text, first_line, style.line_numbers);
+                }
+                Err(err) => log::warn!(
+                    "Failed to read {}, reconstructing the impl block: {}",
+                    file_path.relative_path(),
+                    err
+                ),
+            }
+        }
+    }
+    // only the first line of the header and the closing brace are known to
+    // match the source file
+    let header = first.impl_header.as_deref().unwrap_or_default();
+    let start = impl_block_start(header);
+    let (header_line, end_line) = match span {
+        Some(span) => (Some(span.start.line), Some(span.end.line)),
+        None => (None, None),
+    };
+    let (first_line, rest) = start.split_once('\n').unwrap_or((&start, ""));
+    let mut block = code_lines(first_line, header_line, style.line_numbers);
+    block.push_str(&code_lines(rest, None, style.line_numbers));
+    for method in methods {
+        block.push_str(&method.method_code(style));
+    }
+    block.push_str(&code_lines("}", end_line, style.line_numbers));
+    block
+}
+
+fn indent(code: &str) -> String {
+    code.lines().map(|line| format!("    {}\n", line)).collect()
+}
+
+// e.g. `impl Foo {`, a where clause already ends on its own line
+fn impl_block_start(header: &str) -> String {
+    let separator = if header.contains('\n') { "\n" } else { " " };
+    format!("{}{}{{", header, separator)
+}
+
 fn print_extracted_code(
     block: &syn::Block,
     source_path: &str,
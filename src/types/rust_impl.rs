@@ -1,24 +1,70 @@
 use std::fmt::{Display, Formatter};
 
 use super::{Identifiable, RustFunction, Visibility};
-use crate::helpers::generate_id;
+use crate::helpers::{generate_id, qualify_id};
 use crate::writers::ClippyWriter;
 
 #[derive(Debug, Clone)]
 pub struct RustImpl {
     id: String,
     pub for_type: String,
+    // path of the implementing type, without generics
+    type_path: String,
     pub methods: Option<Vec<RustFunction>>,
 }
 
 impl RustImpl {
-    pub fn new_with_data(for_type: String, methods: Vec<RustFunction>) -> Self {
+    pub fn new_with_data(
+        for_type: String,
+        for_trait: Option<String>,
+        mut methods: Vec<RustFunction>,
+    ) -> Self {
+        let type_path = for_type
+            .split('<')
+            .next()
+            .unwrap_or_default()
+            .replace(' ', "");
+        let target = impl_target(&type_path, for_trait.as_deref());
+        // methods are identified through their impl, e.g. `Foo::new` or
+        // `<Foo as Default>::default`
+        for method in &mut methods {
+            method.qualify_id(&target);
+            method.set_self_type(&type_path);
+        }
         RustImpl {
-            id: generate_id(&for_type),
+            id: generate_id("impl", &target),
             for_type,
+            type_path,
             methods: Some(methods),
         }
     }
+
+    /// Qualified path of the implementing type, e.g. `codeclippy::Foo` for
+    /// `impl<T> Default for Foo<T>`.
+    pub fn type_path(&self) -> &str {
+        &self.type_path
+    }
+
+    /// Add the methods of another block with the same id, e.g. a second
+    /// `impl Foo { .. }` in the same module.
+    pub fn merge(&mut self, other: RustImpl) {
+        let methods = self.methods.get_or_insert_with(Vec::new);
+        for method in other.methods.into_iter().flatten() {
+            if !methods.iter().any(|known| known.id() == method.id()) {
+                methods.push(method);
+            }
+        }
+    }
+
+    /// Prefix the id, and those of its methods, with the module the impl
+    /// is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+        self.type_path = format!("{}::{}", scope, self.type_path);
+        for method in self.methods.iter_mut().flatten() {
+            method.qualify_id(scope);
+        }
+    }
 }
 
 impl Identifiable for RustImpl {
@@ -50,3 +96,13 @@ impl Display for RustImpl {
         write!(f, "impl {} {{\n{}\n}}", self.for_type, methods_str)
     }
 }
+
+fn impl_target(type_path: &str, for_trait: Option<&str>) -> String {
+    match for_trait {
+        // token streams are spaced out, e.g. `From < u32 >`
+        Some(for_trait) => {
+            format!("<{} as {}>", type_path, for_trait.replace(' ', ""))
+        }
+        None => type_path.to_string(),
+    }
+}
@@ -0,0 +1,123 @@
+use std::fmt;
+use std::fmt::{Display, Write};
+
+use super::format::pretty_code_fmt;
+use super::{Attributes, Identifiable, Span, Visibility};
+use crate::helpers::{generate_id, qualify_id};
+use crate::localfs::FilePath;
+use crate::writers::ClippyWriter;
+
+/// A `macro_rules!` definition. Exported macros (`#[macro_export]`) are
+/// considered public, all others are only visible in their own crate.
+#[derive(Debug, Clone)]
+pub struct RustMacro {
+    id: String,
+    visibility: Visibility,
+    name: String,
+    rules: String,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
+}
+
+impl Identifiable for RustMacro {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn print(&self, writer: &mut Box<dyn ClippyWriter>) {
+        let _ = write!(writer, "{}", self);
+    }
+
+    fn visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+}
+
+impl RustMacro {
+    pub fn new_with_data(
+        name: String,
+        visibility: Visibility,
+        rules: String,
+    ) -> Self {
+        RustMacro {
+            id: generate_id("macro", &name),
+            name,
+            visibility,
+            rules,
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Prefix the id with the module the item is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    pub fn rules(&self) -> &str {
+        &self.rules
+    }
+
+    pub fn macro_block_str(&self) -> String {
+        let mut macro_str = String::new();
+        if self.visibility == Visibility::Public {
+            macro_str.push_str("#[macro_export]\n");
+        }
+        writeln!(
+            &mut macro_str,
+            "macro_rules! {} {{ {} }}",
+            self.name, self.rules
+        )
+        .unwrap();
+        pretty_code_fmt(&mut macro_str);
+        macro_str
+    }
+
+    /// The macro without its rules, e.g. `macro_rules! vec { ... }`.
+    pub fn skeleton_str(&self) -> String {
+        let mut macro_str = String::new();
+        if self.visibility == Visibility::Public {
+            macro_str.push_str("#[macro_export]\n");
+        }
+        writeln!(&mut macro_str, "macro_rules! {} {{ ... }}", self.name)
+            .unwrap();
+        macro_str
+    }
+}
+
+impl Display for RustMacro {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.macro_block_str())
+    }
+}
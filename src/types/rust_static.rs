@@ -0,0 +1,126 @@
+use std::fmt;
+use std::fmt::{Display, Write};
+
+use super::format::pretty_code_fmt;
+use super::{Attributes, Identifiable, Span, Visibility};
+use crate::helpers::{generate_id, qualify_id};
+use crate::localfs::FilePath;
+use crate::writers::ClippyWriter;
+
+#[derive(Debug, Clone)]
+pub struct RustStatic {
+    id: String,
+    visibility: Visibility,
+    name: String,
+    mutable: bool,
+    ty: String,
+    expr: String,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
+}
+
+impl Identifiable for RustStatic {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn print(&self, writer: &mut Box<dyn ClippyWriter>) {
+        let _ = write!(writer, "{}", self);
+    }
+
+    fn visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+}
+
+impl RustStatic {
+    pub fn new_with_data(
+        name: String,
+        visibility: Visibility,
+        mutable: bool,
+        ty: String,
+        expr: String,
+    ) -> Self {
+        RustStatic {
+            id: generate_id("static", &name),
+            name,
+            visibility,
+            mutable,
+            ty,
+            expr,
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Prefix the id with the module the item is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    pub fn expr(&self) -> &str {
+        &self.expr
+    }
+
+    pub fn static_block_str(&self) -> String {
+        let mut static_str = String::new();
+        let visibility = if self.visibility.to_string().is_empty() {
+            String::from("")
+        } else {
+            format!("{} ", self.visibility)
+        };
+        writeln!(
+            &mut static_str,
+            "{}static {}{}: {} = {};",
+            visibility,
+            if self.mutable { "mut " } else { "" },
+            self.name,
+            self.ty,
+            self.expr
+        )
+        .unwrap();
+        pretty_code_fmt(&mut static_str);
+        static_str
+    }
+}
+
+impl Display for RustStatic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.static_block_str())
+    }
+}
@@ -1,27 +1,62 @@
 use std::fmt;
 use std::fmt::{Display, Write};
 
-use super::format::pretty_code_fmt;
-use super::{Identifiable, RustFunction, Visibility};
+use super::format::{item_code, pretty_code_fmt, CodeStyle};
+use super::rust_function::impl_blocks_code;
+use super::{Attributes, Identifiable, RustFunction, Span, Visibility};
+use crate::helpers::qualify_id;
+use crate::localfs::FilePath;
 use crate::writers::ClippyWriter;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StructKind {
+    /// `struct Foo { a: u32 }`
+    Named,
+    /// `struct Foo(u32);`, fields are named by position ("0", "1", ..)
+    Tuple,
+    /// `struct Foo;`
+    Unit,
+    /// `union Foo { a: u32 }`
+    Union,
+}
+
+/// Visibility, name and type of a field.
+pub type Field = (Visibility, String, String);
+
 #[derive(Debug, Clone)]
 pub struct RustStruct {
     id: String,
     name: String,
     visibility: Visibility,
-    fields: Option<Vec<(String, String)>>,
+    kind: StructKind,
+    generics: String,
+    where_clause: String,
+    fields: Option<Vec<Field>>,
     methods: Option<Vec<RustFunction>>,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
 }
 
 impl RustStruct {
-    pub fn new(id: &str, visibility: Visibility, name: &str) -> Self {
+    pub fn new(
+        id: &str,
+        visibility: Visibility,
+        name: &str,
+        kind: StructKind,
+    ) -> Self {
         Self {
             id: id.to_string(),
             name: name.to_string(),
             visibility,
+            kind,
+            generics: String::new(),
+            where_clause: String::new(),
             fields: None,
             methods: None,
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
         }
     }
 
@@ -29,6 +64,40 @@ impl RustStruct {
         &self.id
     }
 
+    /// Prefix the id, and those of its methods, with the module the struct
+    /// is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+        for method in self.methods.iter_mut().flatten() {
+            method.qualify_id(scope);
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -37,7 +106,13 @@ impl RustStruct {
         self.methods.as_ref()
     }
 
-    pub fn add_fields(&mut self, fields: Vec<(String, String)>) {
+    /// Generic parameters (e.g. `<T: Clone>`) and where clause, as written.
+    pub fn set_generics(&mut self, generics: String, where_clause: String) {
+        self.generics = generics;
+        self.where_clause = where_clause;
+    }
+
+    pub fn add_fields(&mut self, fields: Vec<Field>) {
         match &mut self.fields {
             Some(existing_fields) => existing_fields.extend(fields),
             None => self.fields = Some(fields),
@@ -53,28 +128,93 @@ impl RustStruct {
 
     pub fn struct_base_block_str(&self) -> String {
         let mut fields_str = String::new();
-        let visibility = if self.visibility.to_string().is_empty() {
-            String::from("")
-        } else {
-            format!("{} ", self.visibility)
-        };
-        write!(&mut fields_str, "{}struct {} {{\n", visibility, self.name)
-            .unwrap();
-        if let Some(fields) = &self.fields {
-            for (field_name, field_type) in fields {
-                write!(
+        let visibility = visibility_prefix(&self.visibility);
+        let fields = self.fields.as_deref().unwrap_or_default();
+        match self.kind {
+            StructKind::Named | StructKind::Union => {
+                let keyword = if self.kind == StructKind::Union {
+                    "union"
+                } else {
+                    "struct"
+                };
+                writeln!(
                     &mut fields_str,
-                    "    {}: {},\n",
-                    field_name, field_type
+                    "{}{} {}{} {} {{",
+                    visibility,
+                    keyword,
+                    self.name,
+                    self.generics,
+                    self.where_clause
+                )
+                .unwrap();
+                for (field_visibility, field_name, field_type) in fields {
+                    writeln!(
+                        &mut fields_str,
+                        "    {}{}: {},",
+                        visibility_prefix(field_visibility),
+                        field_name,
+                        field_type
+                    )
+                    .unwrap();
+                }
+                fields_str.push_str("}\n");
+            }
+            StructKind::Tuple => {
+                let field_types: Vec<String> = fields
+                    .iter()
+                    .map(|(field_visibility, _, field_type)| {
+                        format!(
+                            "{}{}",
+                            visibility_prefix(field_visibility),
+                            field_type
+                        )
+                    })
+                    .collect();
+                writeln!(
+                    &mut fields_str,
+                    "{}struct {}{}({}) {};",
+                    visibility,
+                    self.name,
+                    self.generics,
+                    field_types.join(", "),
+                    self.where_clause
+                )
+                .unwrap();
+            }
+            StructKind::Unit => {
+                writeln!(
+                    &mut fields_str,
+                    "{}struct {}{} {};",
+                    visibility, self.name, self.generics, self.where_clause
                 )
                 .unwrap();
             }
         }
-        write!(&mut fields_str, "}}\n").unwrap();
         pretty_code_fmt(&mut fields_str);
         fields_str
     }
 
+    /// `struct_base_block_str` in the given style.
+    pub fn definition_code(&self, style: CodeStyle) -> String {
+        let reconstructed = || self.struct_base_block_str();
+        item_code(
+            reconstructed,
+            &self.attributes,
+            self.span,
+            self.file_path(),
+            style,
+        )
+    }
+
+    /// The definition followed by the methods in the impl blocks they are
+    /// defined in, all in the given style.
+    pub fn code(&self, style: CodeStyle) -> String {
+        let mut code = self.definition_code(style);
+        let methods = self.methods.as_deref().unwrap_or_default();
+        code.push_str(&impl_blocks_code(methods, style));
+        code
+    }
+
     pub fn struct_impl_block_str(&self) -> String {
         let mut methods_str = String::new();
         if let Some(methods) = &self.methods {
@@ -115,3 +255,12 @@ impl Display for RustStruct {
         write!(f, "{}", struct_str)
     }
 }
+
+// `pub ` for public items, nothing for private ones
+fn visibility_prefix(visibility: &Visibility) -> String {
+    if visibility.to_string().is_empty() {
+        String::new()
+    } else {
+        format!("{} ", visibility)
+    }
+}
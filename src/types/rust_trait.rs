@@ -2,8 +2,9 @@ use std::fmt;
 use std::fmt::{Display, Write};
 
 use super::format::pretty_code_fmt;
-use super::{Identifiable, RustFunction, Visibility};
-use crate::helpers::generate_id;
+use super::{Attributes, Identifiable, RustFunction, Span, Visibility};
+use crate::helpers::{generate_id, qualify_id};
+use crate::localfs::FilePath;
 use crate::writers::ClippyWriter;
 
 #[derive(Debug, Clone)]
@@ -11,7 +12,14 @@ pub struct RustTrait {
     id: String,
     visibility: Visibility,
     name: String,
+    generics: String,
+    where_clause: String,
+    // e.g. `: Display + Clone`
+    supertraits: String,
     methods: Option<Vec<RustFunction>>,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
 }
 
 impl Identifiable for RustTrait {
@@ -39,32 +47,102 @@ impl RustTrait {
         methods: Vec<RustFunction>,
     ) -> Self {
         RustTrait {
-            id: generate_id(&name),
+            id: generate_id("trait", &name),
             name,
             visibility,
+            generics: String::new(),
+            where_clause: String::new(),
+            supertraits: String::new(),
             methods: Some(methods),
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
         }
     }
 
+    /// Prefix the id, and those of its methods, with the module the item
+    /// is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+        for method in self.methods.iter_mut().flatten() {
+            method.qualify_id(scope);
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    /// Generic parameters (e.g. `<T: Clone>`) and where clause, as written.
+    pub fn set_generics(&mut self, generics: String, where_clause: String) {
+        self.generics = generics;
+        self.where_clause = where_clause;
+    }
+
+    pub fn set_supertraits(&mut self, supertraits: String) {
+        self.supertraits = supertraits;
+    }
+
     pub fn methods(&self) -> Option<&Vec<RustFunction>> {
         self.methods.as_ref()
     }
 
     pub fn trait_block_str(&self) -> String {
+        self.block_str(|method| method.to_string())
+    }
+
+    /// The trait with the signatures of its methods only.
+    pub fn skeleton_str(&self) -> String {
+        self.block_str(RustFunction::skeleton_str)
+    }
+
+    fn block_str(
+        &self,
+        method_str: impl Fn(&RustFunction) -> String,
+    ) -> String {
         let mut trait_str = String::new();
         let visibility = if self.visibility.to_string().is_empty() {
             String::from("")
         } else {
             format!("{} ", self.visibility)
         };
-        write!(&mut trait_str, "{}trait {} {{\n", visibility, self.name)
-            .unwrap();
+        writeln!(
+            &mut trait_str,
+            "{}trait {}{}{} {} {{",
+            visibility,
+            self.name,
+            self.generics,
+            self.supertraits,
+            self.where_clause
+        )
+        .unwrap();
         if let Some(methods) = &self.methods {
             for method in methods {
-                write!(&mut trait_str, "    {}", method).unwrap();
+                write!(&mut trait_str, "    {}", method_str(method)).unwrap();
             }
         }
-        write!(&mut trait_str, "}}\n").unwrap();
+        writeln!(&mut trait_str, "}}").unwrap();
         pretty_code_fmt(&mut trait_str);
         trait_str
     }
@@ -1,20 +1,124 @@
-use super::{RustEnum, RustFunction, RustStruct, RustTrait, Visibility};
+use std::fmt;
+use std::fmt::Display;
+
+use super::format::{item_code, CodeStyle};
+use super::{
+    Attributes, RustConst, RustEnum, RustFunction, RustMacro, RustStatic,
+    RustStruct, RustTrait, RustTypeAlias, Span, Visibility,
+};
+use crate::localfs::FilePath;
 use crate::writers::ClippyWriter;
 
 #[derive(Debug, Clone)]
 pub enum RustType {
-    Function(RustFunction),
+    Function(Box<RustFunction>),
     Struct(RustStruct),
     Enum(RustEnum),
     Trait(RustTrait),
+    Const(RustConst),
+    Static(RustStatic),
+    TypeAlias(RustTypeAlias),
+    Macro(RustMacro),
+}
+
+impl RustType {
+    /// Where the item is defined in its source file, if known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            RustType::Function(func) => func.span(),
+            RustType::Struct(strct) => strct.span(),
+            RustType::Enum(enu) => enu.span(),
+            RustType::Trait(trt) => trt.span(),
+            RustType::Const(cnst) => cnst.span(),
+            RustType::Static(stat) => stat.span(),
+            RustType::TypeAlias(alias) => alias.span(),
+            RustType::Macro(mac) => mac.span(),
+        }
+    }
+
+    pub fn attributes(&self) -> &Attributes {
+        match self {
+            RustType::Function(func) => func.attributes(),
+            RustType::Struct(strct) => strct.attributes(),
+            RustType::Enum(enu) => enu.attributes(),
+            RustType::Trait(trt) => trt.attributes(),
+            RustType::Const(cnst) => cnst.attributes(),
+            RustType::Static(stat) => stat.attributes(),
+            RustType::TypeAlias(alias) => alias.attributes(),
+            RustType::Macro(mac) => mac.attributes(),
+        }
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        match self {
+            RustType::Function(func) => func.file_path(),
+            RustType::Struct(strct) => strct.file_path(),
+            RustType::Enum(enu) => enu.file_path(),
+            RustType::Trait(trt) => trt.file_path(),
+            RustType::Const(cnst) => cnst.file_path(),
+            RustType::Static(stat) => stat.file_path(),
+            RustType::TypeAlias(alias) => alias.file_path(),
+            RustType::Macro(mac) => mac.file_path(),
+        }
+    }
+
+    /// `print` in the given style. Line numbers count from the first line
+    /// of the item, reconstructed items that are formatted differently
+    /// than in the source file may drift from there.
+    pub fn print_code(
+        &self,
+        writer: &mut Box<dyn ClippyWriter>,
+        style: CodeStyle,
+    ) {
+        if style == CodeStyle::default() {
+            self.print(writer);
+            return;
+        }
+        let code = match self {
+            // methods are written in their own impl blocks
+            RustType::Struct(strct) => strct.code(style),
+            RustType::Enum(enu) => enu.code(style),
+            rtype => item_code(
+                || match rtype {
+                    RustType::Function(func) if style.skeleton => {
+                        func.skeleton_str()
+                    }
+                    RustType::Trait(trt) if style.skeleton => {
+                        trt.skeleton_str()
+                    }
+                    RustType::Macro(mac) if style.skeleton => {
+                        mac.skeleton_str()
+                    }
+                    rtype => rtype.to_string(),
+                },
+                rtype.attributes(),
+                rtype.span(),
+                rtype.file_path(),
+                style,
+            ),
+        };
+        let _ = write!(writer, "{}", code);
+    }
+}
+
+impl Display for RustType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RustType::Function(func) => write!(f, "{}", func),
+            RustType::Struct(strct) => write!(f, "{}", strct),
+            RustType::Enum(enu) => write!(f, "{}", enu),
+            RustType::Trait(trt) => write!(f, "{}", trt),
+            RustType::Const(cnst) => write!(f, "{}", cnst),
+            RustType::Static(stat) => write!(f, "{}", stat),
+            RustType::TypeAlias(alias) => write!(f, "{}", alias),
+            RustType::Macro(mac) => write!(f, "{}", mac),
+        }
+    }
 }
 
 pub trait Identifiable {
     fn id(&self) -> &str;
     fn name(&self) -> &str;
-    fn as_rust_type(&self) -> Option<&RustType> {
-        None
-    }
     fn methods(&self) -> Option<&Vec<RustFunction>> {
         None
     }
@@ -32,6 +136,10 @@ impl Identifiable for RustType {
             RustType::Struct(strct) => strct.id(),
             RustType::Enum(enu) => enu.id(),
             RustType::Trait(trt) => trt.id(),
+            RustType::Const(cnst) => cnst.id(),
+            RustType::Static(stat) => stat.id(),
+            RustType::TypeAlias(alias) => alias.id(),
+            RustType::Macro(mac) => mac.id(),
         }
     }
 
@@ -41,6 +149,10 @@ impl Identifiable for RustType {
             RustType::Struct(strct) => strct.name(),
             RustType::Enum(enu) => enu.name(),
             RustType::Trait(trt) => trt.name(),
+            RustType::Const(cnst) => cnst.name(),
+            RustType::Static(stat) => stat.name(),
+            RustType::TypeAlias(alias) => alias.name(),
+            RustType::Macro(mac) => mac.name(),
         }
     }
 
@@ -50,6 +162,10 @@ impl Identifiable for RustType {
             RustType::Struct(strct) => strct.print(writer),
             RustType::Enum(enu) => enu.print(writer),
             RustType::Trait(trt) => trt.print(writer),
+            RustType::Const(cnst) => cnst.print(writer),
+            RustType::Static(stat) => stat.print(writer),
+            RustType::TypeAlias(alias) => alias.print(writer),
+            RustType::Macro(mac) => mac.print(writer),
         }
     }
 
@@ -59,11 +175,12 @@ impl Identifiable for RustType {
             RustType::Struct(strct) => strct.visibility(),
             RustType::Enum(enu) => enu.visibility(),
             RustType::Trait(trt) => trt.visibility(),
+            RustType::Const(cnst) => cnst.visibility(),
+            RustType::Static(stat) => stat.visibility(),
+            RustType::TypeAlias(alias) => alias.visibility(),
+            RustType::Macro(mac) => mac.visibility(),
         }
     }
-    fn as_rust_type(&self) -> Option<&RustType> {
-        Some(self)
-    }
 
     fn methods(&self) -> Option<&Vec<RustFunction>> {
         match self {
@@ -71,6 +188,10 @@ impl Identifiable for RustType {
             RustType::Struct(strct) => strct.methods(),
             RustType::Enum(enu) => enu.methods(),
             RustType::Trait(trt) => trt.methods(),
+            RustType::Const(_)
+            | RustType::Static(_)
+            | RustType::TypeAlias(_)
+            | RustType::Macro(_) => None,
         }
     }
 }
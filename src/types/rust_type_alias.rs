@@ -0,0 +1,115 @@
+use std::fmt;
+use std::fmt::{Display, Write};
+
+use super::format::pretty_code_fmt;
+use super::{Attributes, Identifiable, Span, Visibility};
+use crate::helpers::{generate_id, qualify_id};
+use crate::localfs::FilePath;
+use crate::writers::ClippyWriter;
+
+#[derive(Debug, Clone)]
+pub struct RustTypeAlias {
+    id: String,
+    visibility: Visibility,
+    name: String,
+    generics: String,
+    ty: String,
+    span: Option<Span>,
+    file_path: Option<FilePath>,
+    attributes: Attributes,
+}
+
+impl Identifiable for RustTypeAlias {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn print(&self, writer: &mut Box<dyn ClippyWriter>) {
+        let _ = write!(writer, "{}", self);
+    }
+
+    fn visibility(&self) -> &Visibility {
+        &self.visibility
+    }
+}
+
+impl RustTypeAlias {
+    pub fn new_with_data(
+        name: String,
+        visibility: Visibility,
+        generics: String,
+        ty: String,
+    ) -> Self {
+        RustTypeAlias {
+            id: generate_id("type", &name),
+            name,
+            visibility,
+            generics,
+            ty,
+            span: None,
+            file_path: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    /// Prefix the id with the module the item is defined in.
+    pub fn qualify_id(&mut self, scope: &str) {
+        self.id = qualify_id(&self.id, scope);
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.span
+    }
+
+    pub fn set_span(&mut self, span: Span) {
+        self.span = Some(span);
+    }
+
+    pub fn file_path(&self) -> Option<&FilePath> {
+        self.file_path.as_ref()
+    }
+
+    pub fn set_file_path(&mut self, file_path: FilePath) {
+        self.file_path = Some(file_path);
+    }
+
+    /// Doc comment and other outer attributes.
+    pub fn attributes(&self) -> &Attributes {
+        &self.attributes
+    }
+
+    pub fn set_attributes(&mut self, attributes: Attributes) {
+        self.attributes = attributes;
+    }
+
+    pub fn ty(&self) -> &str {
+        &self.ty
+    }
+
+    pub fn type_alias_block_str(&self) -> String {
+        let mut alias_str = String::new();
+        let visibility = if self.visibility.to_string().is_empty() {
+            String::from("")
+        } else {
+            format!("{} ", self.visibility)
+        };
+        writeln!(
+            &mut alias_str,
+            "{}type {}{} = {};",
+            visibility, self.name, self.generics, self.ty
+        )
+        .unwrap();
+        pretty_code_fmt(&mut alias_str);
+        alias_str
+    }
+}
+
+impl Display for RustTypeAlias {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.type_alias_block_str())
+    }
+}
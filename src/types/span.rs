@@ -0,0 +1,67 @@
+use std::fs;
+use std::io;
+
+use proc_macro2::LineColumn;
+
+use crate::localfs::FilePath;
+
+/// Location of an item in its source file. Lines start at 1, columns at 0
+/// (as reported by `proc_macro2`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: LineColumn,
+    pub end: LineColumn,
+    // where the outer attributes and doc comments of the item start,
+    // `start` if there are none
+    pub outer_start: LineColumn,
+}
+
+impl Span {
+    pub fn new(start: LineColumn, end: LineColumn) -> Self {
+        Span {
+            start,
+            end,
+            outer_start: start,
+        }
+    }
+
+    /// Include the outer attributes and doc comments starting at
+    /// `outer_start`.
+    pub fn with_outer_start(mut self, outer_start: LineColumn) -> Self {
+        self.outer_start = outer_start;
+        self
+    }
+
+    /// The text of the item in `file`, including its attributes and doc
+    /// comments. The first line keeps its indentation.
+    pub fn source_text(&self, file: &FilePath) -> io::Result<String> {
+        let content = fs::read_to_string(file.real_path())?;
+        let lines: Vec<&str> = content.lines().collect();
+        let (first, last) = (self.outer_start.line - 1, self.end.line - 1);
+        let Some(lines) = lines.get(first..=last) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "span exceeds file, which may have changed since it was read",
+            ));
+        };
+
+        let mut text = Vec::with_capacity(lines.len());
+        for (index, line) in lines.iter().enumerate() {
+            // columns count characters, not bytes
+            let mut chars: Vec<char> = line.chars().collect();
+            if index == lines.len() - 1 {
+                chars.truncate(self.end.column);
+            }
+            if index == 0 {
+                let (before, _) =
+                    chars.split_at(self.outer_start.column.min(chars.len()));
+                // another item may end on the same line
+                if !before.iter().all(|c| c.is_whitespace()) {
+                    chars.drain(..before.len());
+                }
+            }
+            text.push(chars.into_iter().collect::<String>());
+        }
+        Ok(text.join("\n"))
+    }
+}
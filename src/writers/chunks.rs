@@ -0,0 +1,337 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use serde_json::json;
+
+use super::ClippyWriter;
+use crate::tokenizer::Tokenizer;
+
+/// What the size of a chunk is measured in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitBy {
+    Tokens,
+    Bytes,
+    Items,
+}
+
+impl SplitBy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tokens" => Some(SplitBy::Tokens),
+            "bytes" => Some(SplitBy::Bytes),
+            "items" => Some(SplitBy::Items),
+            _ => None,
+        }
+    }
+}
+
+struct Item {
+    id: Option<String>,
+    // file of the group the item is listed in
+    file: Option<String>,
+    // whether the text starts with the header of the group
+    has_header: bool,
+    text: Vec<u8>,
+}
+
+#[derive(Default)]
+struct Chunk {
+    text: Vec<u8>,
+    ids: Vec<String>,
+    tokens: usize,
+}
+
+/// Splits the output into `chunk-001.txt`, `chunk-002.txt`, ... in a
+/// directory, each at most `size` tokens, bytes or items. Items are never
+/// split, one larger than `size` gets a chunk of its own. A chunk starting
+/// in the middle of a group repeats its header, as `@file:`.
+///
+/// The chunks, and `index.json` listing the items in each of them, are
+/// written on flush, or when dropped without flushing. This replaces the
+/// chunks of an earlier run, even if nothing was written.
+pub struct ChunkWriter {
+    out_dir: PathBuf,
+    split_by: SplitBy,
+    size: usize,
+    tokenizer: Rc<dyn Tokenizer>,
+    item: Item,
+    chunk: Chunk,
+    closed: Vec<Chunk>,
+    // whether anything was written since the last flush, set initially so
+    // an empty output still replaces earlier chunks
+    dirty: bool,
+}
+
+impl ChunkWriter {
+    pub fn new(
+        out_dir: &Path,
+        split_by: SplitBy,
+        size: usize,
+        tokenizer: Rc<dyn Tokenizer>,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(out_dir)?;
+        Ok(Self {
+            out_dir: out_dir.to_path_buf(),
+            split_by,
+            size,
+            tokenizer,
+            item: Item {
+                id: None,
+                file: None,
+                has_header: false,
+                text: Vec::new(),
+            },
+            chunk: Chunk::default(),
+            closed: Vec::new(),
+            dirty: true,
+        })
+    }
+
+    // move the current item to the current chunk, after closing that if
+    // the item does not fit
+    fn place_item(&mut self) {
+        let next = Item {
+            id: None,
+            file: self.item.file.clone(),
+            has_header: false,
+            text: Vec::new(),
+        };
+        let item = std::mem::replace(&mut self.item, next);
+        if item.text.is_empty() {
+            return;
+        }
+        let tokens = self.tokenizer.count(&String::from_utf8_lossy(&item.text));
+        let size = match self.split_by {
+            SplitBy::Tokens => self.chunk.tokens + tokens,
+            SplitBy::Bytes => self.chunk.text.len() + item.text.len(),
+            SplitBy::Items => self.chunk.ids.len() + 1,
+        };
+        if !self.chunk.ids.is_empty() && size > self.size {
+            self.closed.push(std::mem::take(&mut self.chunk));
+        }
+
+        // a new chunk starts with the header of the group
+        if self.chunk.text.is_empty() {
+            if let Some(file) = item.file.as_ref().filter(|_| !item.has_header)
+            {
+                let header = format!("@{}:\n", file);
+                self.chunk.tokens += self.tokenizer.count(&header);
+                self.chunk.text.extend_from_slice(header.as_bytes());
+            }
+        }
+        self.chunk.text.extend_from_slice(&item.text);
+        self.chunk.tokens += tokens;
+        self.chunk.ids.extend(item.id);
+    }
+
+    fn write_chunks(&self) -> io::Result<()> {
+        self.remove_chunks()?;
+        let mut index = Vec::new();
+        for (number, chunk) in self.closed.iter().enumerate() {
+            let file_name = format!("chunk-{:03}.txt", number + 1);
+            fs::write(self.out_dir.join(&file_name), &chunk.text)?;
+            index.push(json!({
+                "file": file_name,
+                "bytes": chunk.text.len(),
+                "tokens": chunk.tokens,
+                "items": chunk.ids,
+            }));
+        }
+        let index = json!({ "chunks": index });
+        fs::write(self.out_dir.join("index.json"), format!("{:#}\n", index))
+    }
+
+    // chunks of an earlier, larger output would otherwise be mistaken for
+    // part of this one
+    fn remove_chunks(&self) -> io::Result<()> {
+        for entry in fs::read_dir(&self.out_dir)? {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap_or_default();
+            if is_chunk_file(&file_name.to_string_lossy()) && path.is_file() {
+                fs::remove_file(&path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// e.g. `chunk-001.txt`, or `chunk-1000.txt` past 999 chunks
+fn is_chunk_file(file_name: &str) -> bool {
+    file_name
+        .strip_prefix("chunk-")
+        .and_then(|rest| rest.strip_suffix(".txt"))
+        .is_some_and(|number| {
+            !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+impl ClippyWriter for ChunkWriter {
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        self.write_all(s.as_bytes())?;
+        self.write_all(b"\n")
+    }
+
+    // buffer is consumed by default
+    fn get_buffer(&self) -> Option<&Vec<u8>> {
+        None
+    }
+
+    fn start_group(&mut self, file: &str) {
+        self.place_item();
+        self.item.file = Some(file.to_string());
+        self.item.has_header = true;
+    }
+
+    fn start_item(&mut self, id: &str) {
+        // text before the first item of a group, i.e. the header, is kept
+        // with that item
+        if self.item.id.is_some() {
+            self.place_item();
+        }
+        self.item.id = Some(id.to_string());
+    }
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.item.text.extend_from_slice(buf);
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.place_item();
+        if !self.chunk.text.is_empty() {
+            self.closed.push(std::mem::take(&mut self.chunk));
+        }
+        self.write_chunks()?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for ChunkWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            eprintln!("Failed to write chunks: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::ApproximateTokenizer;
+
+    fn out_dir(name: &str) -> PathBuf {
+        let out_dir = std::env::temp_dir().join(format!(
+            "codeclippy-chunks-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&out_dir);
+        out_dir
+    }
+
+    fn writer(out_dir: &Path, split_by: SplitBy, size: usize) -> ChunkWriter {
+        let tokenizer = Rc::new(ApproximateTokenizer::new());
+        ChunkWriter::new(out_dir, split_by, size, tokenizer).unwrap()
+    }
+
+    // items of one group, the first starting with its header
+    fn write_group(writer: &mut ChunkWriter, file: &str, ids: &[&str]) {
+        writer.start_group(file);
+        for (index, id) in ids.iter().enumerate() {
+            writer.start_item(id);
+            if index == 0 {
+                writer.write_line(&format!("@{}:", file)).unwrap();
+            }
+            writer.write_line(&format!("fn {}() {{}}", id)).unwrap();
+        }
+    }
+
+    fn read(out_dir: &Path, file_name: &str) -> String {
+        fs::read_to_string(out_dir.join(file_name)).unwrap()
+    }
+
+    #[test]
+    fn packs_items_and_repeats_the_header() {
+        let out_dir = out_dir("items");
+        let mut writer = writer(&out_dir, SplitBy::Items, 2);
+        write_group(&mut writer, "a.rs", &["a", "b", "c"]);
+        writer.flush().unwrap();
+
+        assert_eq!(
+            read(&out_dir, "chunk-001.txt"),
+            "@a.rs:\nfn a() {}\nfn b() {}\n"
+        );
+        assert_eq!(read(&out_dir, "chunk-002.txt"), "@a.rs:\nfn c() {}\n");
+        let index: serde_json::Value =
+            serde_json::from_str(&read(&out_dir, "index.json")).unwrap();
+        assert_eq!(index["chunks"][0]["items"], json!(["a", "b"]));
+        assert_eq!(index["chunks"][1]["items"], json!(["c"]));
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn never_splits_an_item() {
+        let out_dir = out_dir("bytes");
+        let mut writer = writer(&out_dir, SplitBy::Bytes, 8);
+        write_group(&mut writer, "a.rs", &["a"]);
+        write_group(&mut writer, "b.rs", &["b"]);
+        writer.flush().unwrap();
+
+        // each item is larger than a chunk, and gets one of its own
+        assert_eq!(read(&out_dir, "chunk-001.txt"), "@a.rs:\nfn a() {}\n");
+        assert_eq!(read(&out_dir, "chunk-002.txt"), "@b.rs:\nfn b() {}\n");
+        assert!(!out_dir.join("chunk-003.txt").exists());
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn removes_chunks_of_earlier_runs() {
+        let out_dir = out_dir("stale");
+        fs::create_dir_all(&out_dir).unwrap();
+        fs::write(out_dir.join("chunk-002.txt"), "stale").unwrap();
+        fs::write(out_dir.join("notes.txt"), "kept").unwrap();
+
+        let mut writer = writer(&out_dir, SplitBy::Tokens, 1000);
+        write_group(&mut writer, "a.rs", &["a"]);
+        writer.flush().unwrap();
+
+        assert!(out_dir.join("chunk-001.txt").exists());
+        assert!(!out_dir.join("chunk-002.txt").exists());
+        assert_eq!(read(&out_dir, "notes.txt"), "kept");
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn empty_output_replaces_earlier_chunks() {
+        let out_dir = out_dir("empty");
+        fs::create_dir_all(&out_dir).unwrap();
+        fs::write(out_dir.join("chunk-001.txt"), "stale").unwrap();
+
+        let mut writer = writer(&out_dir, SplitBy::Tokens, 1000);
+        writer.flush().unwrap();
+
+        assert!(!out_dir.join("chunk-001.txt").exists());
+        let index: serde_json::Value =
+            serde_json::from_str(&read(&out_dir, "index.json")).unwrap();
+        assert_eq!(index["chunks"], json!([]));
+        fs::remove_dir_all(&out_dir).unwrap();
+    }
+
+    #[test]
+    fn recognizes_chunk_files() {
+        assert!(is_chunk_file("chunk-001.txt"));
+        assert!(is_chunk_file("chunk-1000.txt"));
+        assert!(!is_chunk_file("chunk-.txt"));
+        assert!(!is_chunk_file("chunk-001.md"));
+        assert!(!is_chunk_file("chunk-notes.txt"));
+    }
+}
@@ -1,30 +1,69 @@
+use std::env;
+use std::fs::OpenOptions;
 use std::io::{self, Write};
+use std::process::{Command, Stdio};
 
 use arboard::Clipboard;
 
 use super::ClippyWriter;
 
+/// Collects the output and copies it to the clipboard on `flush`, or when
+/// dropped without flushing.
+///
+/// On Linux `wl-copy` (Wayland) and `xclip` (X11) are tried first, then
+/// the system clipboard, elsewhere just the system clipboard. Finally an
+/// OSC 52 escape sequence asks the terminal to set the clipboard, which
+/// also works over SSH.
 pub struct ClipboardWriter {
-    clipboard: Clipboard,
+    buffer: Vec<u8>,
+    // whether the buffer changed since it was last copied
+    dirty: bool,
 }
 
 impl ClipboardWriter {
     pub fn new() -> Self {
-        let mut clipboard = Clipboard::new().unwrap();
-        // Clear the clipboard content
-        clipboard.set_text(String::new()).unwrap();
-        Self { clipboard }
+        Self {
+            buffer: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    fn commit(&mut self) -> io::Result<()> {
+        let text = String::from_utf8_lossy(&self.buffer).into_owned();
+        let mut failures = Vec::new();
+
+        // On X11 and Wayland the content is served by the process owning
+        // the selection, which ends with this one for the system clipboard.
+        // wl-copy and xclip keep serving it in the background.
+        if cfg!(target_os = "linux") {
+            let commands: [(&str, &[&str]); 2] =
+                [("wl-copy", &[]), ("xclip", &["-selection", "clipboard"])];
+            for (program, args) in commands {
+                match pipe_to_command(program, args, &text) {
+                    Ok(()) => return Ok(()),
+                    Err(e) => failures.push(format!("{}: {}", program, e)),
+                }
+            }
+        }
+        match set_system_clipboard(&text) {
+            Ok(()) => return Ok(()),
+            Err(e) => failures.push(format!("system clipboard: {}", e)),
+        }
+        match set_terminal_clipboard(&text) {
+            Ok(()) => return Ok(()),
+            Err(e) => failures.push(format!("OSC 52: {}", e)),
+        }
+        Err(io::Error::other(format!(
+            "failed to copy to clipboard ({})",
+            failures.join("; ")
+        )))
     }
 }
 
 impl ClippyWriter for ClipboardWriter {
     fn write_line(&mut self, s: &str) -> io::Result<()> {
-        let mut current_text = self.clipboard.get_text().unwrap_or_default();
-        current_text.push_str(s);
-        current_text.push('\n');
-        self.clipboard
-            .set_text(current_text)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+        self.write_all(s.as_bytes())?;
+        self.write_all(b"\n")
     }
 
     // buffer is consumed by default
@@ -35,16 +74,120 @@ impl ClippyWriter for ClipboardWriter {
 
 impl Write for ClipboardWriter {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let mut current_text = self.clipboard.get_text().unwrap_or_default();
-        let new_text = String::from_utf8_lossy(buf);
-        current_text.push_str(&new_text);
-        self.clipboard
-            .set_text(current_text)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.buffer.extend_from_slice(buf);
+        self.dirty = true;
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.commit()?;
+        self.dirty = false;
         Ok(())
     }
 }
+
+impl Drop for ClipboardWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            eprintln!("{}", e);
+        }
+    }
+}
+
+fn set_system_clipboard(text: &str) -> Result<(), arboard::Error> {
+    Clipboard::new()?.set_text(text.to_string())
+}
+
+fn pipe_to_command(program: &str, args: &[&str], text: &str) -> io::Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("exited with {}", status)));
+    }
+    Ok(())
+}
+
+// OSC 52 is written to the controlling terminal, stdout may be redirected
+fn set_terminal_clipboard(text: &str) -> io::Result<()> {
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    let sequence = osc52_sequence(text, env::var_os("TMUX").is_some());
+    tty.write_all(sequence.as_bytes())?;
+    tty.flush()
+}
+
+fn osc52_sequence(text: &str, in_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    // tmux only passes escape sequences on when wrapped
+    if in_tmux {
+        format!("\x1bPtmux;\x1b{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] =
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let triple = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |triple, (index, byte)| {
+                triple | (*byte as u32) << (16 - 8 * index)
+            });
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (triple >> (18 - 6 * index)) & 0x3f;
+                encoded.push(ALPHABET[sextet as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads_partial_chunks() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+    }
+
+    #[test]
+    fn base64_uses_the_whole_alphabet() {
+        assert_eq!(base64(&[0xfb, 0xff, 0xbf]), "+/+/");
+        assert_eq!(base64("é\n".as_bytes()), "w6kK");
+    }
+
+    #[test]
+    fn osc52_sets_the_clipboard() {
+        assert_eq!(osc52_sequence("foo", false), "\x1b]52;c;Zm9v\x07");
+    }
+
+    #[test]
+    fn osc52_is_wrapped_for_tmux() {
+        assert_eq!(
+            osc52_sequence("foo", true),
+            "\x1bPtmux;\x1b\x1b]52;c;Zm9v\x07\x1b\\"
+        );
+    }
+}
@@ -0,0 +1,48 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::ClippyWriter;
+
+pub struct FileWriter {
+    file: BufWriter<File>,
+}
+
+impl FileWriter {
+    /// Replace the content of the file at `path`, creating it if needed.
+    pub fn create(path: &Path) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Self {
+            file: BufWriter::new(file),
+        })
+    }
+
+    /// Add to the end of the file at `path`, creating it if needed.
+    pub fn append(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: BufWriter::new(file),
+        })
+    }
+}
+
+impl ClippyWriter for FileWriter {
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        writeln!(self.file, "{}", s)
+    }
+
+    // buffer is consumed by default
+    fn get_buffer(&self) -> Option<&Vec<u8>> {
+        None
+    }
+}
+
+impl Write for FileWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -1,14 +1,30 @@
 mod buffered;
+mod chunks;
 mod clipboard;
+mod file;
+mod stderr;
 mod stdout;
+mod target;
 
 use std::io::{self, Write};
 
 pub use buffered::BufferedWriter;
+pub use chunks::{ChunkWriter, SplitBy};
 pub use clipboard::ClipboardWriter;
+pub use file::FileWriter;
+pub use stderr::StderrWriter;
 pub use stdout::StdoutWriter;
+pub use target::writer_for_uri;
 
 pub trait ClippyWriter: Write {
     fn write_line(&mut self, s: &str) -> io::Result<()>;
     fn get_buffer(&self) -> Option<&Vec<u8>>;
+
+    /// Marks the start of a group of items from `file`, before the
+    /// `@file:line:` header of the group is written.
+    fn start_group(&mut self, _file: &str) {}
+
+    /// Marks the start of the item with `id`, writers that split their
+    /// output (see `ChunkWriter`) keep it in one piece.
+    fn start_item(&mut self, _id: &str) {}
 }
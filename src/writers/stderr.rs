@@ -0,0 +1,32 @@
+use std::io::{self, Write};
+
+use super::ClippyWriter;
+
+pub struct StderrWriter;
+
+impl StderrWriter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ClippyWriter for StderrWriter {
+    fn write_line(&mut self, s: &str) -> io::Result<()> {
+        writeln!(io::stderr(), "{}", s)
+    }
+
+    // buffer is consumed by default
+    fn get_buffer(&self) -> Option<&Vec<u8>> {
+        None
+    }
+}
+
+impl Write for StderrWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stderr().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stderr().flush()
+    }
+}
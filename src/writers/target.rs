@@ -0,0 +1,126 @@
+use std::io;
+use std::path::Path;
+
+use super::{
+    ClipboardWriter, ClippyWriter, FileWriter, StderrWriter, StdoutWriter,
+};
+
+/// The writer for a `--clip` target:
+///
+/// - `clipboard://`
+/// - `file://path`, replacing the file, or `file://path?append`
+/// - `stdout://` or `-`
+/// - `stderr://`
+pub fn writer_for_uri(uri: &str) -> io::Result<Box<dyn ClippyWriter>> {
+    if uri == "-" {
+        return Ok(Box::new(StdoutWriter::new()));
+    }
+    let Some((scheme, rest)) = uri.split_once("://") else {
+        return Err(invalid_uri(
+            "expected <scheme>://, e.g. clipboard:// or file://out.txt",
+        ));
+    };
+    match scheme {
+        "clipboard" if rest.is_empty() => Ok(Box::new(ClipboardWriter::new())),
+        "stdout" if rest.is_empty() => Ok(Box::new(StdoutWriter::new())),
+        "stderr" if rest.is_empty() => Ok(Box::new(StderrWriter::new())),
+        "clipboard" | "stdout" | "stderr" => {
+            Err(invalid_uri(&format!("{}:// does not take a path", scheme)))
+        }
+        "file" => {
+            let (path, append) = match rest.strip_suffix("?append") {
+                Some(path) => (path, true),
+                None => (rest, false),
+            };
+            if path.is_empty() {
+                return Err(invalid_uri("file:// needs a path"));
+            }
+            let writer = if append {
+                FileWriter::append(Path::new(path))?
+            } else {
+                FileWriter::create(Path::new(path))?
+            };
+            Ok(Box::new(writer))
+        }
+        _ => Err(invalid_uri(&format!(
+            "unsupported scheme '{}', expected clipboard, file, stdout or \
+             stderr",
+            scheme
+        ))),
+    }
+}
+
+fn invalid_uri(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn out_file(name: &str) -> PathBuf {
+        let out_dir = std::env::temp_dir().join(format!(
+            "codeclippy-target-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_dir_all(&out_dir);
+        fs::create_dir_all(&out_dir).unwrap();
+        out_dir.join("out.txt")
+    }
+
+    fn error_of(uri: &str) -> Option<String> {
+        writer_for_uri(uri).err().map(|error| {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidInput, "{}", uri);
+            error.to_string()
+        })
+    }
+
+    #[test]
+    fn accepts_supported_uris() {
+        for uri in ["-", "clipboard://", "stdout://", "stderr://"] {
+            assert_eq!(error_of(uri), None, "{}", uri);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_uris() {
+        let cases = [
+            ("", "expected <scheme>://"),
+            ("out.txt", "expected <scheme>://"),
+            ("clipboard:", "expected <scheme>://"),
+            ("clipboard://x", "clipboard:// does not take a path"),
+            ("stdout://out.txt", "stdout:// does not take a path"),
+            ("stderr://-", "stderr:// does not take a path"),
+            ("file://", "file:// needs a path"),
+            ("file://?append", "file:// needs a path"),
+            ("http://example.com", "unsupported scheme 'http'"),
+            ("://", "unsupported scheme ''"),
+        ];
+        for (uri, expected) in cases {
+            let error = error_of(uri).unwrap_or_default();
+            assert!(error.contains(expected), "{}: {}", uri, error);
+        }
+    }
+
+    #[test]
+    fn file_uris_replace_or_append() {
+        let path = out_file("file");
+        let uri = format!("file://{}", path.display());
+        let append = format!("{}?append", uri);
+        for (uri, line) in [(&uri, "one"), (&append, "two"), (&append, "three")]
+        {
+            let mut writer = writer_for_uri(uri).unwrap();
+            writer.write_line(line).unwrap();
+            writer.flush().unwrap();
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "one\ntwo\nthree\n");
+
+        let mut writer = writer_for_uri(&uri).unwrap();
+        writer.write_line("four").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "four\n");
+    }
+}